@@ -10,9 +10,28 @@ pub struct Program;
 #[program]
 impl Program {
     /// Constructor for the Voting Program.
-    /// Must be called once at deployment, passing the admin and available options.
-    pub fn new(admin: ActorId, options: Vec<String>) -> Self {
-        Service::seed(admin, options);
+    /// Must be called once at deployment, passing the admin, available options,
+    /// an optional allow-list of eligible voters and an optional event indexer program.
+    pub fn new(
+        admin: ActorId,
+        options: Vec<String>,
+        eligible: Option<Vec<ActorId>>,
+        indexer: Option<ActorId>,
+    ) -> Self {
+        Service::seed(admin, options, eligible, indexer);
+        Self
+    }
+
+    /// Constructor for a Yes/No ballot (plus optional Abstain) with a pass threshold
+    /// in basis points of Yes / (Yes + No).
+    pub fn new_binary(
+        admin: ActorId,
+        pass_threshold_bps: u16,
+        with_abstain: bool,
+        eligible: Option<Vec<ActorId>>,
+        indexer: Option<ActorId>,
+    ) -> Self {
+        Service::seed_binary(admin, pass_threshold_bps, with_abstain, eligible, indexer);
         Self
     }
 
@@ -3,10 +3,34 @@
 #![allow(static_mut_refs)]
 
 use sails_rs::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     gstd::{msg, exec},
     prelude::*,
+    calls::ActionIo,
 };
+use extended_vft_client::vft::io as vft_io;
+use extended_vnft_client::vnft::io as vnft_io;
+use pagination::paginate;
+
+// Maximum number of entries returned by a paginated query
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+// Decayed results are expressed in thousandths of a vote
+pub const DECAY_PRECISION: u64 = 1_000;
+
+// Maximum length in bytes of a write-in suggestion
+pub const MAX_WRITE_IN_LENGTH: usize = 64;
+
+// Maximum number of write-in suggestions awaiting approval in a poll
+pub const MAX_PENDING_WRITE_INS: usize = 50;
+
+// Maximum number of multi-sig admins
+pub const MAX_MULTISIG_ADMINS: usize = 32;
+
+// Options of a binary ballot
+pub const YES: &str = "Yes";
+pub const NO: &str = "No";
+pub const ABSTAIN: &str = "Abstain";
 
 // Global static state for the voting contract
 static mut VOTING_STATE: Option<VotingState> = None;
@@ -19,12 +43,141 @@ pub struct VotingState {
     pub votes: HashMap<String, u64>,
     pub has_voted: Vec<ActorId>,
     pub voting_open: bool,
+    pub admins: Vec<ActorId>,
+    pub threshold: u32,
+    pub pending_actions: Vec<PendingAction>,
+    pub eligible: Option<HashSet<ActorId>>,
+    pub stake_token: Option<ActorId>,
+    pub stakes: HashMap<ActorId, (String, U256)>,
+    pub staked_by_option: HashMap<String, U256>,
+    pub total_staked: U256,
+    pub stake_paid: U256,
+    pub pass_threshold_bps: Option<u16>,
+    pub round: u32,
+    pub archive: Vec<ArchivedPoll>,
+    pub credits: HashMap<ActorId, u64>,
+    pub quadratic: bool,
+    pub quadratic_votes: HashMap<(ActorId, String), u64>,
+    pub allow_write_in: bool,
+    pub pending_options: HashMap<String, ActorId>,
+    pub indexer: Option<ActorId>,
+    pub decay_half_life_ms: Option<u64>,
+    pub vote_log: Vec<TimedVote>,
+    pub option_managers: HashSet<ActorId>,
+    pub closers: HashSet<ActorId>,
+    pub execution: Option<ExecutionPayload>,
+    pub executed: bool,
+    pub option_caps: HashMap<String, u64>,
+    pub voting_power: Option<VotingPower>,
+    pub snapshot_block: u32,
+    pub counted_tokens: HashMap<U256, ActorId>,
+    pub hide_results_until_close: bool,
+}
+
+// Programs whose holdings weight a vote: power = VFT votes at the poll's snapshot block
+// + NFTs held and not yet counted in the poll * per_nft_weight
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct VotingPower {
+    pub vft: Option<ActorId>,
+    pub vnft: Option<ActorId>,
+    pub per_nft_weight: u64,
+}
+
+// Call sent to `target` when a passing ballot is executed. `method` is the `/`-separated
+// Sails route (e.g. "Vft/Mint"); each segment is SCALE-encoded and followed by `args`,
+// which must already be SCALE-encoded.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct ExecutionPayload {
+    pub target: ActorId,
+    pub method: String,
+    pub args: Vec<u8>,
+}
+
+// A batch of votes cast at a given time, used for time-decayed results
+#[derive(Clone, Default)]
+pub struct TimedVote {
+    pub voter: ActorId,
+    pub option: String,
+    pub votes: u64,
+    pub timestamp: u64,
+}
+
+// Flat result row for reports and exports
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct ResultRow {
+    pub option: String,
+    pub votes: u64,
+    pub percent_bps: u32,
+    pub rank: u32,
+}
+
+// Final results of a finished round, kept after `reset`
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct ArchivedPoll {
+    pub round: u32,
+    pub results: Vec<(String, u64)>,
+}
+
+// Delegated admin powers
+#[derive(Encode, Decode, TypeInfo, Clone, Copy, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum Role {
+    OptionManager,
+    Closer,
+}
+
+// Admin and delegated actions; once multi-sig is enabled they all go through approval
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum AdminAction {
+    CloseVoting,
+    AddOption(String),
+    RemoveOption(String),
+    ApproveWriteIn(String),
+    SetOptionCap { option: String, cap: u64 },
+    SetAllowWriteIn(bool),
+    SetHideResults(bool),
+    SetStakeToken(ActorId),
+    SweepStakes(ActorId),
+    SetVotingPower(VotingPower),
+    SetQuadratic(bool),
+    SetCredits { voter: ActorId, credits: u64 },
+    SetDecayHalfLife(Option<u64>),
+    SetExecution(ExecutionPayload),
+    Reset(Vec<String>),
+    AddEligible(ActorId),
+    RemoveEligible(ActorId),
+    SetRole { role: Role, account: ActorId, granted: bool },
+}
+
+// An admin action waiting for enough distinct approvals
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct PendingAction {
+    pub action: AdminAction,
+    pub approvals: Vec<ActorId>,
 }
 
 // Methods related to VotingState
 impl VotingState {
     // Initialize contract state; can only be called once
-    pub fn init(admin: ActorId, options: Vec<String>) {
+    pub fn init(
+        admin: ActorId,
+        options: Vec<String>,
+        eligible: Option<Vec<ActorId>>,
+        indexer: Option<ActorId>,
+    ) {
         unsafe {
             VOTING_STATE = Some(Self {
                 admin,
@@ -32,10 +185,152 @@ impl VotingState {
                 votes: options.into_iter().map(|opt| (opt, 0u64)).collect(),
                 has_voted: Vec::new(),
                 voting_open: true,
+                eligible: eligible.map(|voters| voters.into_iter().collect()),
+                indexer,
+                option_managers: [admin].into(),
+                closers: [admin].into(),
+                snapshot_block: exec::block_height().saturating_sub(1),
+                ..Default::default()
             });
         }
     }
 
+    // Everyone is eligible unless an allow-list is configured
+    pub fn is_eligible(&self, who: &ActorId) -> bool {
+        match &self.eligible {
+            Some(eligible) => eligible.contains(who),
+            None => true,
+        }
+    }
+
+    // Actions are taken directly only by holders of the power while multi-sig is off;
+    // afterwards every action goes through proposals
+    pub fn check_direct(&self, allowed: bool, error: &str) -> Result<(), String> {
+        if !allowed {
+            return Err(error.to_string());
+        }
+        if self.threshold > 0 {
+            return Err("Multi-sig is enabled, use proposals".to_string());
+        }
+        Ok(())
+    }
+
+    // Check that `voter` may cast a vote worth `weight` votes on `option` right now
+    pub fn check_vote(&self, voter: &ActorId, option: &str, weight: u64) -> Result<(), String> {
+        // Check voting is open
+        if !self.voting_open {
+            return Err("Voting is closed".to_string());
+        }
+        // Check the user has not voted yet; quadratic voters may spread votes over calls
+        if !self.quadratic && self.has_voted.contains(voter) {
+            return Err("Already voted".to_string());
+        }
+        // Check the option exists
+        if !self.options.iter().any(|o| o == option) {
+            return Err("Invalid option".to_string());
+        }
+        // Check the voter is on the allow-list, if there is one
+        if !self.is_eligible(voter) {
+            return Err("Not eligible".to_string());
+        }
+        // Check the option still has room for the whole weight, without naming the
+        // capped option while results are hidden
+        if self.option_remaining(option).is_some_and(|remaining| weight > remaining) {
+            if self.published_option(option.to_string()).is_none() {
+                return Err("Vote exceeds a cap".to_string());
+            }
+            return Err("Option full".to_string());
+        }
+        Ok(())
+    }
+
+    // Whether anyone voted in this poll yet, including votes still awaiting replies
+    pub fn voting_started(&self) -> bool {
+        !self.has_voted.is_empty() || !self.vote_log.is_empty()
+    }
+
+    // Interim results are visible to everyone unless hidden until close; the admin always sees them.
+    // Query callers choose their own origin and program state is public, so this only keeps
+    // the standard queries quiet; it is not confidentiality.
+    pub fn results_visible_to(&self, who: &ActorId) -> bool {
+        !self.hide_results_until_close || !self.voting_open || *who == self.admin
+    }
+
+    // Option to publish in vote events and indexer forwards; left out while results are hidden
+    pub fn published_option(&self, option: String) -> Option<String> {
+        (!self.hide_results_until_close || !self.voting_open).then_some(option)
+    }
+
+    // Votes an option can still take before reaching its cap; None if uncapped
+    pub fn option_remaining(&self, option: &str) -> Option<u64> {
+        let cap = self.option_caps.get(option)?;
+        let count = self.votes.get(option).copied().unwrap_or(0);
+        Some(cap.saturating_sub(count))
+    }
+
+    // Count a vote; the caller must have checked it with `check_vote`
+    pub fn tally(&mut self, voter: ActorId, option: &str) {
+        self.tally_weighted(voter, option, 1);
+    }
+
+    // Count a vote worth `weight` votes; the caller must have checked it with `check_vote`
+    pub fn tally_weighted(&mut self, voter: ActorId, option: &str, weight: u64) {
+        self.add_votes(voter, option, weight);
+        self.has_voted.push(voter);
+    }
+
+    // Undo a vote counted with `tally` or `tally_weighted`
+    pub fn untally(&mut self, voter: &ActorId, option: &str) {
+        if let Some(index) = self
+            .vote_log
+            .iter()
+            .rposition(|vote| vote.voter == *voter && vote.option == option)
+        {
+            let vote = self.vote_log.remove(index);
+            if let Some(count) = self.votes.get_mut(option) {
+                *count = count.saturating_sub(vote.votes);
+            }
+        }
+        self.has_voted.retain(|v| v != voter);
+        self.counted_tokens.retain(|_, counted_by| counted_by != voter);
+    }
+
+    // Add `votes` to an option's tally and log them with the current time
+    pub fn add_votes(&mut self, voter: ActorId, option: &str, votes: u64) {
+        let count = self.votes.get_mut(option).expect("No such option");
+        *count = count.saturating_add(votes);
+        self.vote_log.push(TimedVote {
+            voter,
+            option: option.to_string(),
+            votes,
+            timestamp: exec::block_timestamp(),
+        });
+    }
+
+    // Outcome of a closed binary ballot; None while open or for multi-option polls
+    pub fn passed(&self) -> Option<bool> {
+        let threshold = self.pass_threshold_bps?;
+        if self.voting_open {
+            return None;
+        }
+        let yes = self.votes.get(YES).copied().unwrap_or(0) as u128;
+        let no = self.votes.get(NO).copied().unwrap_or(0) as u128;
+        if yes + no == 0 {
+            return Some(false);
+        }
+        Some(yes * 10_000 >= threshold as u128 * (yes + no))
+    }
+
+    // Options with the highest vote count (several on a tie)
+    pub fn winning_options(&self) -> Vec<String> {
+        let max = self.votes.values().copied().max().unwrap_or(0);
+        self.options
+            .iter()
+            .filter(|option| self.votes.get(*option) == Some(&max))
+            .cloned()
+            .collect()
+    }
+
     // Get a mutable reference to the state
     pub fn state_mut() -> &'static mut VotingState {
         let state = unsafe { VOTING_STATE.as_mut() };
@@ -60,6 +355,8 @@ pub struct IoVotingState {
     pub options: Vec<String>,
     pub votes: Vec<(String, u64)>,
     pub voting_open: bool,
+    pub admins: Vec<ActorId>,
+    pub threshold: u32,
 }
 
 // Convert internal state to queryable state struct
@@ -70,18 +367,43 @@ impl From<VotingState> for IoVotingState {
             options: state.options.clone(),
             votes: state.votes.iter().map(|(k, v)| (k.clone(), *v)).collect(),
             voting_open: state.voting_open,
+            admins: state.admins.clone(),
+            threshold: state.threshold,
         }
     }
 }
 
 // Events for off-chain tracking
-#[derive(Encode, Decode, TypeInfo)]
+#[derive(Encode, Decode, TypeInfo, Clone)]
 #[codec(crate = sails_rs::scale_codec)]
 #[scale_info(crate = sails_rs::scale_info)]
 pub enum Events {
-    VoteCast { voter: ActorId, option: String },
+    VoteCast { voter: ActorId, option: Option<String> },
+    QuadraticVoteCast { voter: ActorId, option: Option<String>, votes: u64, cost: u64 },
+    CreditsSet { voter: ActorId, credits: u64 },
     VotingClosed,
+    VotingReset { round: u32 },
+    ExecutionSet(ExecutionPayload),
+    Executed { target: ActorId, method: String },
     OptionAdded(String),
+    OptionRemoved(String),
+    OptionCapSet { option: String, cap: u64 },
+    RoleGranted { role: Role, account: ActorId },
+    RoleRevoked { role: Role, account: ActorId },
+    WriteInSuggested { by: ActorId, option: String },
+    WriteInToggled(bool),
+    QuadraticToggled(bool),
+    ResultsHiddenToggled(bool),
+    DecaySet(Option<u64>),
+    EligibleAdded(ActorId),
+    EligibleRemoved(ActorId),
+    StakeTokenSet(ActorId),
+    VotingPowerSet(VotingPower),
+    StakeDeposited { voter: ActorId, amount: U256 },
+    StakeClaimed { voter: ActorId, amount: U256 },
+    StakesSwept { to: ActorId, amount: U256 },
+    MultisigEnabled { threshold: u32 },
+    ActionApproved { action: AdminAction, approvals: u32 },
     Error(String),
 }
 
@@ -97,110 +419,624 @@ impl Service {
         Self
     }
 
-    /// Seed function to initialize voting state (call EXACTLY once)
-    pub fn seed(admin: ActorId, options: Vec<String>) {
-        // Validate options are not empty and unique
-        if options.is_empty() {
-            panic!("No voting options provided");
+    /// Seed function to initialize voting state (call EXACTLY once).
+    /// `eligible` restricts voting to the given actors; `None` keeps voting open to all.
+    /// `indexer` receives a copy of every emitted event.
+    pub fn seed(
+        admin: ActorId,
+        options: Vec<String>,
+        eligible: Option<Vec<ActorId>>,
+        indexer: Option<ActorId>,
+    ) {
+        if let Err(error) = validate_options(&options) {
+            panic!("{}", error);
         }
-        let mut seen = Vec::new();
-        for option in options.iter() {
-            if seen.contains(option) {
-                panic!("Duplicate voting options are not allowed");
-            }
-            seen.push(option.clone());
+        VotingState::init(admin, options, eligible, indexer);
+    }
+
+    /// Seed a Yes/No ballot (optionally with Abstain) that passes when
+    /// Yes / (Yes + No) reaches `pass_threshold_bps` basis points.
+    pub fn seed_binary(
+        admin: ActorId,
+        pass_threshold_bps: u16,
+        with_abstain: bool,
+        eligible: Option<Vec<ActorId>>,
+        indexer: Option<ActorId>,
+    ) {
+        if pass_threshold_bps > 10_000 {
+            panic!("Pass threshold cannot exceed 10000 bps");
+        }
+        let mut options = vec![YES.to_string(), NO.to_string()];
+        if with_abstain {
+            options.push(ABSTAIN.to_string());
         }
-        VotingState::init(admin, options);
+        VotingState::init(admin, options, eligible, indexer);
+        VotingState::state_mut().pass_threshold_bps = Some(pass_threshold_bps);
     }
 
     /// Cast a vote on an option. Fails if voting is closed or sender already voted.
-    pub fn vote(&mut self, option: String) -> Events {
+    /// With weighted voting configured, the vote counts as the sender's voting power (see
+    /// `VotingPower`) and senders without any power are rejected.
+    pub async fn vote(&mut self, option: String) -> Events {
         let sender = msg::source();
-        let voting = VotingState::state_mut();
 
-        // Check voting is open
-        if !voting.voting_open {
-            return Events::Error("Voting is closed".to_string());
+        if let Err(error) = self.cast_vote(sender, &option).await {
+            return Events::Error(error);
         }
-        // Check the user has not voted yet
-        if voting.has_voted.contains(&sender) {
-            return Events::Error("Already voted".to_string());
+
+        let option = VotingState::state_ref().published_option(option);
+        self.notify(Events::VoteCast {
+            voter: sender,
+            option: option.clone(),
+        });
+        Events::VoteCast { voter: sender, option }
+    }
+
+    /// Cast a vote like `vote` and stake VFT tokens of the configured token program on it.
+    /// The stake is refunded with a share of the losers' stakes if the option wins.
+    pub async fn vote_and_stake(&mut self, option: String, amount: U256) -> Events {
+        let sender = msg::source();
+        let voting = VotingState::state_mut();
+
+        let Some(token) = voting.stake_token else {
+            return Events::Error("Staking is not configured".to_string());
+        };
+        if amount.is_zero() {
+            return Events::Error("Stake cannot be zero".to_string());
         }
-        // Check the option exists
-        if !voting.options.contains(&option) {
-            return Events::Error("Invalid option".to_string());
+
+        // The vote is counted before the transfer is awaited, so the voter cannot vote
+        // twice meanwhile
+        if let Err(error) = self.cast_vote(sender, &option).await {
+            return Events::Error(error);
         }
-        let count = voting.votes.get_mut(&option).expect("No such option");
-        *count = count.saturating_add(1);
+        let voting = VotingState::state_mut();
+        voting.stakes.insert(sender, (option.clone(), amount));
+        let staked = voting.staked_by_option.entry(option.clone()).or_default();
+        *staked = staked.saturating_add(amount);
+        voting.total_staked = voting.total_staked.saturating_add(amount);
 
-        voting.has_voted.push(sender);
+        if !vft_transfer_from(token, sender, exec::program_id(), amount).await {
+            voting.untally(&sender, &option);
+            voting.stakes.remove(&sender);
+            let staked = voting.staked_by_option.entry(option).or_default();
+            *staked = staked.saturating_sub(amount);
+            voting.total_staked = voting.total_staked.saturating_sub(amount);
+            return Events::Error("Stake transfer failed".to_string());
+        }
 
-        self.emit_event(Events::VoteCast {
+        self.notify(Events::StakeDeposited { voter: sender, amount });
+        let option = VotingState::state_ref().published_option(option);
+        self.notify(Events::VoteCast {
             voter: sender,
             option: option.clone(),
-        })
-        .expect("Event error");
+        });
         Events::VoteCast { voter: sender, option }
     }
 
-    /// Only admin can add an option while voting is still open.
+    /// After close, a voter who staked on a winning option claims the stake back plus a
+    /// pro-rata share of the stakes placed on losing options. Tied winners share the pool.
+    pub async fn claim_stake(&mut self) -> Events {
+        self.pay_out_stake(msg::source()).await
+    }
+
+    /// Pay out a winning voter's stake as `claim_stake` would; anyone can trigger it, so
+    /// idle winners never hold up the next round.
+    pub async fn claim_stake_for(&mut self, voter: ActorId) -> Events {
+        self.pay_out_stake(voter).await
+    }
+
+    /// Only admin can send the stakes left after close to `to`, once every winning stake
+    /// was paid out: forfeited stakes when nobody staked on a winning option, otherwise the
+    /// rounding left over from the pro-rata shares.
+    pub async fn sweep_stakes(&mut self, to: ActorId) -> Events {
+        let voting = VotingState::state_ref();
+        let admin = msg::source() == voting.admin;
+        if let Err(error) = voting.check_direct(admin, "Only admin can sweep stakes") {
+            return Events::Error(error);
+        }
+        self.sweep_stakes_to(to).await
+    }
+
+    /// Only admin can set the VFT program used for vote staking, before anyone staked.
+    pub fn set_stake_token(&mut self, token: ActorId) -> Events {
+        let admin = msg::source() == VotingState::state_ref().admin;
+        self.direct(admin, "Only admin can set the stake token", AdminAction::SetStakeToken(token))
+    }
+
+    /// Only admin can configure weighted voting, before any vote is cast. Passing no VFT
+    /// and no VNFT program switches back to one vote per voter.
+    pub fn set_voting_power(
+        &mut self,
+        vft: Option<ActorId>,
+        vnft: Option<ActorId>,
+        per_nft_weight: u64,
+    ) -> Events {
+        let admin = msg::source() == VotingState::state_ref().admin;
+        let power = VotingPower {
+            vft,
+            vnft,
+            per_nft_weight,
+        };
+        self.direct(admin, "Only admin can set voting power", AdminAction::SetVotingPower(power))
+    }
+
+    /// Only admin can switch the poll between one vote per voter and quadratic voting,
+    /// before the first vote. The modes are exclusive; weighted voting can't be combined
+    /// with quadratic voting.
+    pub fn set_quadratic(&mut self, enabled: bool) -> Events {
+        let admin = msg::source() == VotingState::state_ref().admin;
+        self.direct(admin, "Only admin can set the tally mode", AdminAction::SetQuadratic(enabled))
+    }
+
+    /// Cast `votes` quadratic votes on an option in a quadratic poll, paid from the sender's
+    /// credit budget. Holding `n` votes on an option costs `n^2` credits in total, so adding
+    /// votes to an option charges the difference. Votes may be spread over several options.
+    pub fn vote_quadratic(&mut self, option: String, votes: u64) -> Events {
+        let sender = msg::source();
+        let voting = VotingState::state_mut();
+
+        if !voting.quadratic {
+            return Events::Error("Quadratic voting is disabled".to_string());
+        }
+        if votes == 0 {
+            return Events::Error("Votes cannot be zero".to_string());
+        }
+        if let Err(error) = voting.check_vote(&sender, &option, votes) {
+            return Events::Error(error);
+        }
+
+        let key = (sender, option.clone());
+        let held = voting.quadratic_votes.get(&key).copied().unwrap_or(0);
+        let cost = held
+            .checked_add(votes)
+            .and_then(|total| total.checked_mul(total))
+            .map(|total_cost| total_cost - held * held);
+        let budget = voting.credits.get(&sender).copied().unwrap_or(0);
+        let Some(cost) = cost.filter(|cost| *cost <= budget) else {
+            return Events::Error("Insufficient credits".to_string());
+        };
+
+        voting.credits.insert(sender, budget - cost);
+        voting.quadratic_votes.insert(key, held + votes);
+        voting.add_votes(sender, &option, votes);
+        if !voting.has_voted.contains(&sender) {
+            voting.has_voted.push(sender);
+        }
+
+        let option = voting.published_option(option);
+        self.notify(Events::QuadraticVoteCast {
+            voter: sender,
+            option: option.clone(),
+            votes,
+            cost,
+        });
+        Events::QuadraticVoteCast { voter: sender, option, votes, cost }
+    }
+
+    /// Only admin can set the credit budget of a voter for quadratic voting.
+    pub fn set_credits(&mut self, voter: ActorId, credits: u64) -> Events {
+        let admin = msg::source() == VotingState::state_ref().admin;
+        self.direct(admin, "Only admin can set credits", AdminAction::SetCredits { voter, credits })
+    }
+
+    /// Only admin can configure the half-life used by `query_results_decayed`; `None` disables decay.
+    pub fn set_decay_half_life(&mut self, half_life_ms: Option<u64>) -> Events {
+        let admin = msg::source() == VotingState::state_ref().admin;
+        self.direct(admin, "Only admin can set decay", AdminAction::SetDecayHalfLife(half_life_ms))
+    }
+
+    /// Only option managers can add an option while voting is still open.
     pub fn add_option(&mut self, option: String) -> Events {
+        let allowed = VotingState::state_ref().option_managers.contains(&msg::source());
+        self.direct(allowed, "Only option managers can add options", AdminAction::AddOption(option))
+    }
+
+    /// Only admin can cap the votes an option may receive, while voting is open.
+    pub fn set_option_cap(&mut self, option: String, cap: u64) -> Events {
+        let admin = msg::source() == VotingState::state_ref().admin;
+        let action = AdminAction::SetOptionCap { option, cap };
+        self.direct(admin, "Only admin can set option caps", action)
+    }
+
+    /// Only option managers can remove an option that has not received any votes yet.
+    pub fn remove_option(&mut self, option: String) -> Events {
+        let allowed = VotingState::state_ref().option_managers.contains(&msg::source());
+        let action = AdminAction::RemoveOption(option);
+        self.direct(allowed, "Only option managers can remove options", action)
+    }
+
+    /// Suggest a new option while write-ins are allowed; only eligible voters can suggest.
+    /// The suggestion waits for admin approval and does not receive votes until then.
+    /// Suggestions are at most `MAX_WRITE_IN_LENGTH` bytes and at most
+    /// `MAX_PENDING_WRITE_INS` of them can await approval.
+    pub fn write_in(&mut self, text: String) -> Events {
         let sender = msg::source();
         let voting = VotingState::state_mut();
 
-        if sender != voting.admin {
-            return Events::Error("Only admin can add options".to_string());
+        if !voting.allow_write_in {
+            return Events::Error("Write-ins are disabled".to_string());
         }
         if !voting.voting_open {
             return Events::Error("Voting must be open".to_string());
         }
-        if option.is_empty() {
+        if !voting.is_eligible(&sender) {
+            return Events::Error("Not eligible".to_string());
+        }
+        if text.is_empty() {
             return Events::Error("Option cannot be empty".to_string());
         }
-        if voting.options.contains(&option) {
+        if text.len() > MAX_WRITE_IN_LENGTH {
+            return Events::Error("Option is too long".to_string());
+        }
+        if voting.options.contains(&text) {
             return Events::Error("Option already exists".to_string());
         }
+        if voting.pending_options.contains_key(&text) {
+            return Events::Error("Already suggested".to_string());
+        }
+        if voting.pending_options.len() >= MAX_PENDING_WRITE_INS {
+            return Events::Error("Too many pending write-ins".to_string());
+        }
+        voting.pending_options.insert(text.clone(), sender);
+
+        self.notify(Events::WriteInSuggested {
+            by: sender,
+            option: text.clone(),
+        });
+        Events::WriteInSuggested { by: sender, option: text }
+    }
+
+    /// Only option managers can promote a write-in suggestion to a real option.
+    pub fn approve_write_in(&mut self, text: String) -> Events {
+        let allowed = VotingState::state_ref().option_managers.contains(&msg::source());
+        let action = AdminAction::ApproveWriteIn(text);
+        self.direct(allowed, "Only option managers can approve write-ins", action)
+    }
 
-        voting.options.push(option.clone());
-        voting.votes.insert(option.clone(), 0u64);
+    /// Only admin can allow or forbid write-in suggestions. Binary ballots have fixed options.
+    pub fn set_allow_write_in(&mut self, allow: bool) -> Events {
+        let admin = msg::source() == VotingState::state_ref().admin;
+        self.direct(admin, "Only admin can toggle write-ins", AdminAction::SetAllowWriteIn(allow))
+    }
 
-        self.emit_event(Events::OptionAdded(option.clone()))
-            .expect("Event error");
-        Events::OptionAdded(option)
+    /// Only admin can hide interim results from everyone else until voting closes. Vote
+    /// events and indexer forwards then leave out the chosen option. Hiding is cosmetic: the
+    /// tally is still in public program state.
+    pub fn set_hide_results_until_close(&mut self, hide: bool) -> Events {
+        let admin = msg::source() == VotingState::state_ref().admin;
+        self.direct(admin, "Only admin can hide results", AdminAction::SetHideResults(hide))
     }
 
-    /// Close the voting (only admin). Once closed, voting cannot be reopened.
+    /// Close the voting (only closers). Once closed, the round cannot be reopened; use `reset` to start a new one.
     pub fn close_voting(&mut self) -> Events {
+        let allowed = VotingState::state_ref().closers.contains(&msg::source());
+        self.direct(allowed, "Only closers can close voting", AdminAction::CloseVoting)
+    }
+
+    /// Only admin can attach the call to enact if the binary ballot passes, while voting is
+    /// open and before the first vote, so voters always know what they are voting for.
+    pub fn set_execution(&mut self, payload: ExecutionPayload) -> Events {
+        let admin = msg::source() == VotingState::state_ref().admin;
+        self.direct(admin, "Only admin can set the execution", AdminAction::SetExecution(payload))
+    }
+
+    /// Send the attached call once the ballot closed and passed. Anyone can trigger it,
+    /// and it runs only once; a failed call can be retried.
+    pub async fn execute(&mut self) -> Events {
+        let voting = VotingState::state_mut();
+
+        let Some(payload) = voting.execution.clone() else {
+            return Events::Error("Nothing to execute".to_string());
+        };
+        if voting.executed {
+            return Events::Error("Already executed".to_string());
+        }
+        if voting.passed() != Some(true) {
+            return Events::Error("Ballot did not pass".to_string());
+        }
+
+        let mut request = Vec::new();
+        for segment in payload.method.split('/') {
+            request.extend(segment.encode());
+        }
+        request.extend_from_slice(&payload.args);
+
+        // Mark as executed before awaiting so it cannot run twice meanwhile
+        voting.executed = true;
+        let succeeded = match msg::send_bytes_for_reply(payload.target, request, 0, 0) {
+            Ok(reply) => reply.await.is_ok(),
+            Err(_) => false,
+        };
+        if !succeeded {
+            voting.executed = false;
+            return Events::Error("Execution failed".to_string());
+        }
+
+        let event = Events::Executed {
+            target: payload.target,
+            method: payload.method,
+        };
+        self.notify(event.clone());
+        event
+    }
+
+    /// Only admin can start a new round once the current one is closed and every stake was
+    /// paid out or swept. The previous results are archived and quadratic credits cleared;
+    /// a binary ballot keeps its fixed options and takes none.
+    pub fn reset(&mut self, options: Vec<String>) -> Events {
+        let admin = msg::source() == VotingState::state_ref().admin;
+        self.direct(admin, "Only admin can reset voting", AdminAction::Reset(options))
+    }
+
+    /// Only admin can add an eligible voter, before the poll's first vote. Adding to a poll
+    /// open to all turns it into an allow-list poll containing only this voter.
+    pub fn add_eligible(&mut self, who: ActorId) -> Events {
+        let admin = msg::source() == VotingState::state_ref().admin;
+        self.direct(admin, "Only admin can manage eligible voters", AdminAction::AddEligible(who))
+    }
+
+    /// Only admin can remove an eligible voter from the allow-list, before the poll's first vote.
+    pub fn remove_eligible(&mut self, who: ActorId) -> Events {
+        let admin = msg::source() == VotingState::state_ref().admin;
+        let action = AdminAction::RemoveEligible(who);
+        self.direct(admin, "Only admin can manage eligible voters", action)
+    }
+
+    /// Only admin can let an actor add, remove and approve options.
+    pub fn grant_option_manager(&mut self, to: ActorId) -> Events {
+        self.set_role(Role::OptionManager, to, true)
+    }
+
+    /// Only admin can take option powers away from an actor.
+    pub fn revoke_option_manager(&mut self, from: ActorId) -> Events {
+        self.set_role(Role::OptionManager, from, false)
+    }
+
+    /// Only admin can let an actor close the voting.
+    pub fn grant_closer(&mut self, to: ActorId) -> Events {
+        self.set_role(Role::Closer, to, true)
+    }
+
+    /// Only admin can take the closing power away from an actor.
+    pub fn revoke_closer(&mut self, from: ActorId) -> Events {
+        self.set_role(Role::Closer, from, false)
+    }
+
+    /// Only admin can enable multi-sig mode. Afterwards every admin and delegated action
+    /// (see `AdminAction`) requires `threshold` distinct approvals from `admins`, at most
+    /// `MAX_MULTISIG_ADMINS` of them. Cannot be undone.
+    pub fn set_multisig(&mut self, admins: Vec<ActorId>, threshold: u32) -> Events {
         let sender = msg::source();
         let voting = VotingState::state_mut();
 
         if sender != voting.admin {
-            return Events::Error("Only admin can close voting".to_string());
+            return Events::Error("Only admin can set multi-sig".to_string());
         }
-        if !voting.voting_open {
-            return Events::Error("Voting already closed".to_string());
+        if voting.threshold > 0 {
+            return Events::Error("Multi-sig already enabled".to_string());
+        }
+        if admins.len() > MAX_MULTISIG_ADMINS {
+            return Events::Error("Too many admins".to_string());
+        }
+        if threshold == 0 || threshold as usize > admins.len() {
+            return Events::Error("Invalid threshold".to_string());
+        }
+        let mut seen = Vec::new();
+        for admin in admins.iter() {
+            if seen.contains(admin) {
+                return Events::Error("Duplicate admins are not allowed".to_string());
+            }
+            seen.push(*admin);
         }
-        voting.voting_open = false;
 
-        self.emit_event(Events::VotingClosed)
-            .expect("Event error");
-        Events::VotingClosed
+        voting.admins = admins;
+        voting.threshold = threshold;
+
+        self.notify(Events::MultisigEnabled { threshold });
+        Events::MultisigEnabled { threshold }
     }
 
-    /// Query: Returns list of options and their current vote counts
-    pub fn query_results(&self) -> Vec<(String, u64)> {
+    /// Multi-sig admin approves closing the voting; executes once the threshold is reached.
+    pub async fn propose_close(&mut self) -> Events {
+        self.propose(AdminAction::CloseVoting).await
+    }
+
+    /// Multi-sig admin approves any admin action; executes once the threshold is reached.
+    /// The action is checked when it executes, and a rejected action has to be proposed again.
+    pub async fn propose_action(&mut self, action: AdminAction) -> Events {
+        self.propose(action).await
+    }
+
+    /// Multi-sig admin approves adding an option; executes once the threshold is reached.
+    pub async fn propose_add_option(&mut self, option: String) -> Events {
+        let voting = VotingState::state_ref();
+
+        if voting.pass_threshold_bps.is_some() {
+            return Events::Error("Options are fixed in a binary ballot".to_string());
+        }
+        if option.is_empty() {
+            return Events::Error("Option cannot be empty".to_string());
+        }
+        if voting.options.contains(&option) {
+            return Events::Error("Option already exists".to_string());
+        }
+        self.propose(AdminAction::AddOption(option)).await
+    }
+
+    /// Query: Returns write-in suggestions awaiting approval and who suggested them
+    pub fn query_pending_options(&self) -> Vec<(String, ActorId)> {
         VotingState::state_ref()
+            .pending_options
+            .iter()
+            .map(|(k, v)| (k.clone(), *v))
+            .collect()
+    }
+
+    /// Query: Returns the actors allowed to manage options
+    pub fn query_option_managers(&self) -> Vec<ActorId> {
+        VotingState::state_ref().option_managers.iter().copied().collect()
+    }
+
+    /// Query: Returns the actors allowed to close the voting
+    pub fn query_closers(&self) -> Vec<ActorId> {
+        VotingState::state_ref().closers.iter().copied().collect()
+    }
+
+    /// Query: Returns the admin actions waiting for approvals
+    pub fn query_pending_actions(&self) -> Vec<PendingAction> {
+        VotingState::state_ref().pending_actions.clone()
+    }
+
+    /// Query: Returns list of options and their current vote counts; empty for non-admins
+    /// while results are hidden until close
+    pub fn query_results(&self) -> Vec<(String, u64)> {
+        let voting = VotingState::state_ref();
+        if !voting.results_visible_to(&msg::source()) {
+            return Vec::new();
+        }
+        voting
             .votes
             .iter()
             .map(|(k, v)| (k.clone(), *v))
             .collect()
     }
 
+    /// Query: Returns the tally in thousandths of a vote, each vote weighted by its age:
+    /// halved every half-life and interpolated linearly in between. Without a configured
+    /// half-life this is the raw tally scaled by `DECAY_PRECISION`.
+    pub fn query_results_decayed(&self) -> Vec<(String, u64)> {
+        let voting = VotingState::state_ref();
+        if !voting.results_visible_to(&msg::source()) {
+            return Vec::new();
+        }
+        let now = exec::block_timestamp();
+        let mut results: HashMap<String, u64> =
+            voting.options.iter().map(|option| (option.clone(), 0u64)).collect();
+
+        for vote in voting.vote_log.iter() {
+            let full = vote.votes.saturating_mul(DECAY_PRECISION);
+            let weight = match voting.decay_half_life_ms {
+                Some(half_life) => decayed(full, now.saturating_sub(vote.timestamp), half_life),
+                None => full,
+            };
+            if let Some(total) = results.get_mut(&vote.option) {
+                *total = total.saturating_add(weight);
+            }
+        }
+        results.into_iter().collect()
+    }
+
+    /// Query: Returns one row per option sorted by rank, most votes first. Tied options
+    /// share a rank (1, 2, 2, 4) and keep the order in which they were added.
+    pub fn query_results_export(&self) -> Vec<ResultRow> {
+        let voting = VotingState::state_ref();
+        if !voting.results_visible_to(&msg::source()) {
+            return Vec::new();
+        }
+        let mut counts: Vec<(String, u64)> = voting
+            .options
+            .iter()
+            .map(|option| (option.clone(), voting.votes.get(option).copied().unwrap_or(0)))
+            .collect();
+        // Stable sort keeps insertion order among ties
+        counts.sort_by(|a, b| b.1.cmp(&a.1));
+
+        let total: u128 = counts.iter().map(|(_, votes)| *votes as u128).sum();
+        let mut rows: Vec<ResultRow> = Vec::with_capacity(counts.len());
+        for (index, (option, votes)) in counts.into_iter().enumerate() {
+            let rank = match rows.last() {
+                Some(previous) if previous.votes == votes => previous.rank,
+                _ => index as u32 + 1,
+            };
+            let percent_bps = if total == 0 {
+                0
+            } else {
+                (votes as u128 * 10_000 / total) as u32
+            };
+            rows.push(ResultRow {
+                option,
+                votes,
+                percent_bps,
+                rank,
+            });
+        }
+        rows
+    }
+
+    /// Query: Returns how many more votes an option can take; None if it has no cap or
+    /// results are hidden from the caller
+    pub fn query_option_remaining(&self, option: String) -> Option<u64> {
+        let voting = VotingState::state_ref();
+        if !voting.results_visible_to(&msg::source()) {
+            return None;
+        }
+        voting.option_remaining(&option)
+    }
+
+    /// Query: Returns a page of `query_results_export` rows plus the number of options
+    pub fn query_results_page(&self, start: u32, limit: u32) -> (Vec<ResultRow>, u32) {
+        paginate(self.query_results_export().into_iter(), start, limit, MAX_PAGE_SIZE)
+    }
+
     /// Query: Returns the voting options (without vote counts)
     pub fn query_options(&self) -> Vec<String> {
         VotingState::state_ref().options.clone()
     }
 
+    /// Query: Returns true if the actor is allowed to vote
+    pub fn query_is_eligible(&self, who: ActorId) -> bool {
+        VotingState::state_ref().is_eligible(&who)
+    }
+
+    /// Query: Returns the option and amount staked by a voter, if any. The option is left
+    /// out while results are hidden from the caller.
+    pub fn query_stake(&self, voter: ActorId) -> Option<(Option<String>, U256)> {
+        let voting = VotingState::state_ref();
+        let (option, amount) = voting.stakes.get(&voter).cloned()?;
+        let visible = voting.results_visible_to(&msg::source());
+        Some((visible.then_some(option), amount))
+    }
+
+    /// Query: For a closed binary ballot, returns whether Yes met the pass threshold.
+    /// A ballot without Yes or No votes (e.g. all abstain) does not pass.
+    /// Returns None while voting is open or for multi-option polls.
+    pub fn query_passed(&self) -> Option<bool> {
+        VotingState::state_ref().passed()
+    }
+
+    /// Query: Returns the results of previous rounds, oldest first
+    pub fn query_archive(&self) -> Vec<ArchivedPoll> {
+        VotingState::state_ref().archive.clone()
+    }
+
+    /// Query: Returns true if the poll uses quadratic voting
+    pub fn query_quadratic(&self) -> bool {
+        VotingState::state_ref().quadratic
+    }
+
+    /// Query: Returns the remaining quadratic voting credits of a voter
+    pub fn query_credits(&self, who: ActorId) -> u64 {
+        VotingState::state_ref().credits.get(&who).copied().unwrap_or(0)
+    }
+
+    /// Query: Returns the attached execution and whether it already ran
+    pub fn query_execution(&self) -> (Option<ExecutionPayload>, bool) {
+        let voting = VotingState::state_ref();
+        (voting.execution.clone(), voting.executed)
+    }
+
+    /// Query: Returns a page of previous rounds, oldest first, plus the number of rounds
+    pub fn query_archive_page(&self, start: u32, limit: u32) -> (Vec<ArchivedPoll>, u32) {
+        let archive = VotingState::state_ref().archive.iter().cloned();
+        paginate(archive, start, limit, MAX_PAGE_SIZE)
+    }
+
+    /// Query: Returns the block whose VFT vote checkpoints weight the current poll
+    pub fn query_snapshot_block(&self) -> u32 {
+        VotingState::state_ref().snapshot_block
+    }
+
     /// Query: Returns true if voting is open, false otherwise
     pub fn query_voting_open(&self) -> bool {
         VotingState::state_ref().voting_open
@@ -208,6 +1044,504 @@ impl Service {
 
     /// Query: Returns the entire state for frontends
     pub fn query_state(&self) -> IoVotingState {
-        VotingState::state_ref().clone().into()
+        let voting = VotingState::state_ref();
+        let mut state: IoVotingState = voting.clone().into();
+        if !voting.results_visible_to(&msg::source()) {
+            state.votes.clear();
+        }
+        state
+    }
+}
+
+impl Service {
+    // Emit an event and forward a copy to the indexer, if any. Outgoing messages are
+    // only dispatched once the current execution succeeds, so the indexer never sees
+    // uncommitted state. Send failures are ignored so they never block governance.
+    fn notify(&mut self, event: Events) {
+        if let Some(indexer) = VotingState::state_ref().indexer {
+            let _ = msg::send(indexer, &event, 0);
+        }
+        self.emit_event(event).expect("Event error");
+    }
+
+    // Pay `voter`'s winning stake plus its share of the losing stakes back to them
+    async fn pay_out_stake(&mut self, voter: ActorId) -> Events {
+        let voting = VotingState::state_mut();
+
+        if voting.voting_open {
+            return Events::Error("Voting is still open".to_string());
+        }
+        let Some(token) = voting.stake_token else {
+            return Events::Error("Staking is not configured".to_string());
+        };
+        let Some((option, stake)) = voting.stakes.get(&voter).cloned() else {
+            return Events::Error("No stake to claim".to_string());
+        };
+        let winners = voting.winning_options();
+        if !winners.contains(&option) {
+            return Events::Error("Stake forfeited".to_string());
+        }
+
+        let winners_staked = winners
+            .iter()
+            .filter_map(|winner| voting.staked_by_option.get(winner))
+            .fold(U256::zero(), |acc, staked| acc.saturating_add(*staked));
+        let reward_pool = voting.total_staked.saturating_sub(winners_staked);
+        let amount = stake.saturating_add(reward_pool.saturating_mul(stake) / winners_staked);
+
+        // Remove the stake and book the payout before awaiting so it cannot be paid twice
+        voting.stakes.remove(&voter);
+        voting.stake_paid = voting.stake_paid.saturating_add(amount);
+        if !vft_transfer(token, voter, amount).await {
+            voting.stakes.insert(voter, (option, stake));
+            voting.stake_paid = voting.stake_paid.saturating_sub(amount);
+            return Events::Error("Stake transfer failed".to_string());
+        }
+
+        self.notify(Events::StakeClaimed { voter, amount });
+        Events::StakeClaimed { voter, amount }
+    }
+
+    // Check and count `voter`'s vote on `option`, weighted by their voting power if weighted
+    // voting is configured. Returns the counted weight.
+    async fn cast_vote(&mut self, voter: ActorId, option: &str) -> Result<u64, String> {
+        let voting = VotingState::state_mut();
+        if voting.quadratic {
+            return Err("Poll uses quadratic voting".to_string());
+        }
+        voting.check_vote(&voter, option, 1)?;
+        let Some(power) = voting.voting_power.clone() else {
+            voting.tally(voter, option);
+            return Ok(1);
+        };
+
+        // Mark the voter before awaiting so they cannot vote twice meanwhile
+        let round = voting.round;
+        voting.has_voted.push(voter);
+        let holdings = voting_power_of(&power, voter, voting.snapshot_block).await;
+        voting.has_voted.retain(|v| *v != voter);
+
+        let Some((votes, token_ids)) = holdings else {
+            return Err("Voting power query failed".to_string());
+        };
+        if voting.round != round {
+            return Err("Poll changed".to_string());
+        }
+        // Each NFT counts once per poll, so passing it on doesn't grant another vote
+        let token_ids: Vec<U256> = token_ids
+            .into_iter()
+            .filter(|token_id| !voting.counted_tokens.contains_key(token_id))
+            .collect();
+        let nft_votes = U256::from(token_ids.len()).saturating_mul(U256::from(power.per_nft_weight));
+        let weight = votes.saturating_add(nft_votes).min(U256::from(u64::MAX)).low_u64();
+        if weight == 0 {
+            return Err("No voting power".to_string());
+        }
+        // The poll may have changed while waiting for the replies
+        voting.check_vote(&voter, option, weight)?;
+        voting.tally_weighted(voter, option, weight);
+        voting
+            .counted_tokens
+            .extend(token_ids.into_iter().map(|token_id| (token_id, voter)));
+        Ok(weight)
+    }
+
+    // Grant or revoke a delegated role; admin only
+    fn set_role(&mut self, role: Role, account: ActorId, granted: bool) -> Events {
+        let admin = msg::source() == VotingState::state_ref().admin;
+        let action = AdminAction::SetRole {
+            role,
+            account,
+            granted,
+        };
+        self.direct(admin, "Only admin can manage roles", action)
+    }
+
+    // Take `action` directly if `allowed` and multi-sig is off
+    fn direct(&mut self, allowed: bool, error: &str, action: AdminAction) -> Events {
+        if let Err(error) = VotingState::state_ref().check_direct(allowed, error) {
+            return Events::Error(error);
+        }
+        self.apply(action)
+    }
+
+    // Send the stakes left after close to `to`
+    async fn sweep_stakes_to(&mut self, to: ActorId) -> Events {
+        let voting = VotingState::state_mut();
+
+        if voting.voting_open {
+            return Events::Error("Voting is still open".to_string());
+        }
+        let Some(token) = voting.stake_token else {
+            return Events::Error("Staking is not configured".to_string());
+        };
+        let winners = voting.winning_options();
+        if voting.stakes.values().any(|(option, _)| winners.contains(option)) {
+            return Events::Error("Unclaimed stakes remain".to_string());
+        }
+        let amount = voting.total_staked.saturating_sub(voting.stake_paid);
+        if amount.is_zero() {
+            return Events::Error("Nothing to sweep".to_string());
+        }
+
+        // Book the payout before awaiting so it cannot be swept twice
+        voting.stake_paid = voting.total_staked;
+        if !vft_transfer(token, to, amount).await {
+            voting.stake_paid = voting.stake_paid.saturating_sub(amount);
+            return Events::Error("Stake transfer failed".to_string());
+        }
+
+        self.notify(Events::StakesSwept { to, amount });
+        Events::StakesSwept { to, amount }
+    }
+
+    // Check and take an admin action; the caller's authorization was already checked
+    fn apply(&mut self, action: AdminAction) -> Events {
+        let voting = VotingState::state_mut();
+
+        let event = match action {
+            AdminAction::CloseVoting => {
+                if !voting.voting_open {
+                    return Events::Error("Voting already closed".to_string());
+                }
+                voting.voting_open = false;
+                voting.pending_actions.clear();
+                Events::VotingClosed
+            }
+            AdminAction::AddOption(option) => {
+                if voting.pass_threshold_bps.is_some() {
+                    return Events::Error("Options are fixed in a binary ballot".to_string());
+                }
+                if !voting.voting_open {
+                    return Events::Error("Voting must be open".to_string());
+                }
+                if option.is_empty() {
+                    return Events::Error("Option cannot be empty".to_string());
+                }
+                if voting.options.contains(&option) {
+                    return Events::Error("Option already exists".to_string());
+                }
+                voting.options.push(option.clone());
+                voting.votes.insert(option.clone(), 0u64);
+                Events::OptionAdded(option)
+            }
+            AdminAction::RemoveOption(option) => {
+                if voting.pass_threshold_bps.is_some() {
+                    return Events::Error("Options are fixed in a binary ballot".to_string());
+                }
+                if !voting.voting_open {
+                    return Events::Error("Voting must be open".to_string());
+                }
+                match voting.votes.get(&option) {
+                    None => return Events::Error("Invalid option".to_string()),
+                    Some(count) if *count > 0 => {
+                        return Events::Error("Option already has votes".to_string())
+                    }
+                    Some(_) => {}
+                }
+                if voting.options.len() == 1 {
+                    return Events::Error("Cannot remove the last option".to_string());
+                }
+                voting.options.retain(|o| *o != option);
+                voting.votes.remove(&option);
+                voting.option_caps.remove(&option);
+                Events::OptionRemoved(option)
+            }
+            AdminAction::ApproveWriteIn(text) => {
+                if !voting.voting_open {
+                    return Events::Error("Voting must be open".to_string());
+                }
+                if voting.pending_options.remove(&text).is_none() {
+                    return Events::Error("No such write-in".to_string());
+                }
+                voting.options.push(text.clone());
+                voting.votes.insert(text.clone(), 0u64);
+                Events::OptionAdded(text)
+            }
+            AdminAction::SetOptionCap { option, cap } => {
+                if !voting.voting_open {
+                    return Events::Error("Voting must be open".to_string());
+                }
+                if !voting.options.contains(&option) {
+                    return Events::Error("Invalid option".to_string());
+                }
+                voting.option_caps.insert(option.clone(), cap);
+                Events::OptionCapSet { option, cap }
+            }
+            AdminAction::SetAllowWriteIn(allow) => {
+                if allow && voting.pass_threshold_bps.is_some() {
+                    return Events::Error("Options are fixed in a binary ballot".to_string());
+                }
+                voting.allow_write_in = allow;
+                Events::WriteInToggled(allow)
+            }
+            AdminAction::SetHideResults(hide) => {
+                voting.hide_results_until_close = hide;
+                Events::ResultsHiddenToggled(hide)
+            }
+            AdminAction::SetStakeToken(token) => {
+                if !voting.total_staked.is_zero() {
+                    return Events::Error("Staking already started".to_string());
+                }
+                voting.stake_token = Some(token);
+                Events::StakeTokenSet(token)
+            }
+            AdminAction::SweepStakes(_) => {
+                return Events::Error("Stakes are swept asynchronously".to_string());
+            }
+            AdminAction::SetVotingPower(power) => {
+                if voting.voting_started() {
+                    return Events::Error("Voting already started".to_string());
+                }
+                if voting.quadratic {
+                    return Events::Error("Poll uses quadratic voting".to_string());
+                }
+                let weighted = power.vft.is_some() || power.vnft.is_some();
+                voting.voting_power = weighted.then(|| power.clone());
+                Events::VotingPowerSet(power)
+            }
+            AdminAction::SetQuadratic(enabled) => {
+                if voting.voting_started() {
+                    return Events::Error("Voting already started".to_string());
+                }
+                if enabled && voting.voting_power.is_some() {
+                    return Events::Error("Weighted voting is enabled".to_string());
+                }
+                voting.quadratic = enabled;
+                Events::QuadraticToggled(enabled)
+            }
+            AdminAction::SetCredits { voter, credits } => {
+                voting.credits.insert(voter, credits);
+                Events::CreditsSet { voter, credits }
+            }
+            AdminAction::SetDecayHalfLife(half_life_ms) => {
+                if half_life_ms == Some(0) {
+                    return Events::Error("Half-life cannot be zero".to_string());
+                }
+                voting.decay_half_life_ms = half_life_ms;
+                Events::DecaySet(half_life_ms)
+            }
+            AdminAction::SetExecution(payload) => {
+                if voting.pass_threshold_bps.is_none() {
+                    return Events::Error("Execution requires a binary ballot".to_string());
+                }
+                if !voting.voting_open {
+                    return Events::Error("Voting must be open".to_string());
+                }
+                if voting.voting_started() {
+                    return Events::Error("Voting already started".to_string());
+                }
+                voting.execution = Some(payload.clone());
+                Events::ExecutionSet(payload)
+            }
+            AdminAction::Reset(options) => {
+                if voting.voting_open {
+                    return Events::Error("Voting must be closed".to_string());
+                }
+                if voting.total_staked > voting.stake_paid {
+                    return Events::Error("Unclaimed stakes remain".to_string());
+                }
+                let options = if voting.pass_threshold_bps.is_some() {
+                    if !options.is_empty() {
+                        return Events::Error("Options are fixed in a binary ballot".to_string());
+                    }
+                    voting.options.clone()
+                } else {
+                    if let Err(error) = validate_options(&options) {
+                        return Events::Error(error);
+                    }
+                    options
+                };
+                let results = voting
+                    .options
+                    .iter()
+                    .map(|option| (option.clone(), voting.votes.get(option).copied().unwrap_or(0)))
+                    .collect();
+                voting.archive.push(ArchivedPoll {
+                    round: voting.round,
+                    results,
+                });
+                voting.round = voting.round.saturating_add(1);
+
+                voting.votes = options.iter().map(|opt| (opt.clone(), 0u64)).collect();
+                voting.options = options;
+                voting.has_voted.clear();
+                voting.counted_tokens.clear();
+                voting.snapshot_block = exec::block_height().saturating_sub(1);
+                voting.quadratic_votes.clear();
+                voting.vote_log.clear();
+                voting.pending_options.clear();
+                voting.pending_actions.clear();
+                voting.stakes.clear();
+                voting.staked_by_option.clear();
+                voting.total_staked = U256::zero();
+                voting.stake_paid = U256::zero();
+                voting.credits.clear();
+                voting.execution = None;
+                voting.executed = false;
+                voting.option_caps.clear();
+                voting.voting_open = true;
+                Events::VotingReset { round: voting.round }
+            }
+            AdminAction::AddEligible(who) => {
+                if voting.voting_started() {
+                    return Events::Error("Voting already started".to_string());
+                }
+                if !voting.eligible.get_or_insert_with(HashSet::new).insert(who) {
+                    return Events::Error("Already eligible".to_string());
+                }
+                Events::EligibleAdded(who)
+            }
+            AdminAction::RemoveEligible(who) => {
+                if voting.voting_started() {
+                    return Events::Error("Voting already started".to_string());
+                }
+                let Some(eligible) = voting.eligible.as_mut() else {
+                    return Events::Error("Voting is open to all".to_string());
+                };
+                if !eligible.remove(&who) {
+                    return Events::Error("Not eligible".to_string());
+                }
+                Events::EligibleRemoved(who)
+            }
+            AdminAction::SetRole {
+                role,
+                account,
+                granted,
+            } => {
+                let holders = match role {
+                    Role::OptionManager => &mut voting.option_managers,
+                    Role::Closer => &mut voting.closers,
+                };
+                if granted {
+                    holders.insert(account);
+                    Events::RoleGranted { role, account }
+                } else {
+                    holders.remove(&account);
+                    Events::RoleRevoked { role, account }
+                }
+            }
+        };
+
+        self.notify(event.clone());
+        event
+    }
+
+    // Record the sender's approval for `action` and execute it once enough admins agreed
+    async fn propose(&mut self, action: AdminAction) -> Events {
+        let sender = msg::source();
+        let voting = VotingState::state_mut();
+
+        if voting.threshold == 0 {
+            return Events::Error("Multi-sig is not enabled".to_string());
+        }
+        if !voting.admins.contains(&sender) {
+            return Events::Error("Only multi-sig admins can propose".to_string());
+        }
+
+        let index = match voting.pending_actions.iter().position(|p| p.action == action) {
+            Some(index) => index,
+            None => {
+                voting.pending_actions.push(PendingAction {
+                    action: action.clone(),
+                    approvals: Vec::new(),
+                });
+                voting.pending_actions.len() - 1
+            }
+        };
+        let pending = &mut voting.pending_actions[index];
+        if pending.approvals.contains(&sender) {
+            return Events::Error("Already approved".to_string());
+        }
+        pending.approvals.push(sender);
+
+        let approvals = pending.approvals.len() as u32;
+        if approvals < voting.threshold {
+            self.notify(Events::ActionApproved {
+                action: action.clone(),
+                approvals,
+            });
+            return Events::ActionApproved { action, approvals };
+        }
+
+        voting.pending_actions.remove(index);
+        match action {
+            AdminAction::SweepStakes(to) => self.sweep_stakes_to(to).await,
+            action => self.apply(action),
+        }
+    }
+}
+
+// Weight remaining of `weight` after `elapsed` ms: halved every `half_life` ms,
+// linear between two halvings
+fn decayed(weight: u64, elapsed: u64, half_life: u64) -> u64 {
+    let halvings = elapsed / half_life;
+    if halvings >= 64 {
+        return 0;
+    }
+    let start = weight >> halvings;
+    let step = (start / 2) as u128 * (elapsed % half_life) as u128 / half_life as u128;
+    start - step as u64
+}
+
+// Check that poll options are not empty and unique
+fn validate_options(options: &[String]) -> Result<(), String> {
+    if options.is_empty() {
+        return Err("No voting options provided".to_string());
+    }
+    let mut seen = Vec::new();
+    for option in options.iter() {
+        if seen.contains(option) {
+            return Err("Duplicate voting options are not allowed".to_string());
+        }
+        seen.push(option.clone());
+    }
+    Ok(())
+}
+
+// Move `value` tokens of a VFT program from `from` to `to`; false if the call failed
+async fn vft_transfer_from(token: ActorId, from: ActorId, to: ActorId, value: U256) -> bool {
+    let request = vft_io::TransferFrom::encode_call(from, to, value);
+    let Ok(reply) = msg::send_bytes_for_reply(token, request, 0, 0) else {
+        return false;
+    };
+    match reply.await {
+        Ok(reply) => vft_io::TransferFrom::decode_reply(reply).unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+// Holdings of `voter` that weight a vote: the VFT votes delegated to them at the end of
+// `block`, read from the token program's `GetVotesAt` checkpoints, and the ids of the NFTs
+// they hold now; None if a query failed
+async fn voting_power_of(power: &VotingPower, voter: ActorId, block: u32) -> Option<(U256, Vec<U256>)> {
+    let mut votes = U256::zero();
+    if let Some(vft) = power.vft {
+        let mut request = "Vft".encode();
+        request.extend("GetVotesAt".encode());
+        request.extend((voter, block).encode());
+        let reply = msg::send_bytes_for_reply(vft, request, 0, 0).ok()?.await.ok()?;
+        let (_, _, value) = <(String, String, U256)>::decode(&mut reply.as_slice()).ok()?;
+        votes = value;
+    }
+    let mut token_ids = Vec::new();
+    if let Some(vnft) = power.vnft {
+        let request = vnft_io::TokensForOwner::encode_call(voter);
+        let reply = msg::send_bytes_for_reply(vnft, request, 0, 0).ok()?.await.ok()?;
+        let tokens = vnft_io::TokensForOwner::decode_reply(reply).ok()?;
+        token_ids = tokens.into_iter().map(|(token_id, _)| token_id).collect();
+    }
+    Some((votes, token_ids))
+}
+
+// Send `value` tokens of a VFT program held by this program to `to`; false if the call failed
+async fn vft_transfer(token: ActorId, to: ActorId, value: U256) -> bool {
+    let request = vft_io::Transfer::encode_call(to, value);
+    let Ok(reply) = msg::send_bytes_for_reply(token, request, 0, 0) else {
+        return false;
+    };
+    match reply.await {
+        Ok(reply) => vft_io::Transfer::decode_reply(reply).unwrap_or(false),
+        Err(_) => false,
     }
 }
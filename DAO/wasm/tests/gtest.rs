@@ -0,0 +1,868 @@
+use app::services::service::{
+    AdminAction, ArchivedPoll, Events, ExecutionPayload, PendingAction, MAX_PENDING_WRITE_INS,
+    MAX_WRITE_IN_LENGTH,
+};
+use extended_vnft_client::TokenMetadata;
+use sails_rs::{
+    gtest::{Program, System, WasmProgram},
+    prelude::*,
+};
+use wasm::WASM_BINARY;
+
+const PROGRAM_ID: u64 = 100;
+const VFT_ID: u64 = 200;
+const VNFT_ID: u64 = 201;
+const TARGET_ID: u64 = 202;
+
+const ADMIN: u64 = 42;
+const ALICE: u64 = 43;
+const BOB: u64 = 44;
+const CAROL: u64 = 45;
+
+fn actor(id: u64) -> ActorId {
+    ActorId::from(id)
+}
+
+fn options(options: &[&str]) -> Vec<String> {
+    options.iter().map(|option| option.to_string()).collect()
+}
+
+fn system() -> System {
+    let system = System::new();
+    for user in [ADMIN, ALICE, BOB, CAROL] {
+        system.mint_to(user, 100_000_000_000_000);
+    }
+    system
+}
+
+// Deploy the voting program through the constructor `ctor`, with ADMIN as admin
+fn deploy<'a>(system: &'a System, ctor: &str, args: impl Encode) -> Program<'a> {
+    let program = Program::from_binary_with_id(system, PROGRAM_ID, WASM_BINARY);
+    let mut payload = ctor.encode();
+    payload.extend(args.encode());
+    let message_id = program.send_bytes(ADMIN, payload);
+    assert!(system.run_next_block().succeed.contains(&message_id));
+    program
+}
+
+// A poll on options A and B, open to all
+fn poll(system: &System) -> Program<'_> {
+    let no_eligible: Option<Vec<ActorId>> = None;
+    let no_indexer: Option<ActorId> = None;
+    deploy(
+        system,
+        "New",
+        (actor(ADMIN), options(&["A", "B"]), no_eligible, no_indexer),
+    )
+}
+
+// Call `method` of the voting service and decode its reply
+fn call<T: Decode>(
+    system: &System,
+    program: &Program,
+    from: u64,
+    method: &str,
+    args: impl Encode,
+) -> T {
+    let mut payload = ("Service", method).encode();
+    payload.extend(args.encode());
+    let message_id = program.send_bytes(from, payload);
+    let result = system.run_next_block();
+    assert!(result.succeed.contains(&message_id), "{} failed", method);
+    let reply = result
+        .log()
+        .iter()
+        .find(|log| log.reply_to() == Some(message_id))
+        .expect("No reply");
+    let (_, _, value) = <(String, String, T)>::decode(&mut reply.payload()).expect("Invalid reply");
+    value
+}
+
+fn send(system: &System, program: &Program, from: u64, method: &str, args: impl Encode) -> Events {
+    call(system, program, from, method, args)
+}
+
+fn assert_error(event: Events, expected: &str) {
+    match event {
+        Events::Error(error) => assert_eq!(error, expected),
+        _ => panic!("Expected error {:?}", expected),
+    }
+}
+
+fn results(system: &System, program: &Program) -> Vec<(String, u64)> {
+    let mut results: Vec<(String, u64)> = call(system, program, ADMIN, "QueryResults", ());
+    results.sort();
+    results
+}
+
+// Token program answering the calls the voting program makes with fixed results
+#[derive(Debug, Clone)]
+struct MockVft {
+    votes: U256,
+    transfers_succeed: bool,
+}
+
+impl WasmProgram for MockVft {
+    fn init(&mut self, _payload: Vec<u8>) -> Result<Option<Vec<u8>>, &'static str> {
+        Ok(None)
+    }
+
+    fn handle(&mut self, payload: Vec<u8>) -> Result<Option<Vec<u8>>, &'static str> {
+        let (service, method) =
+            <(String, String)>::decode(&mut payload.as_slice()).map_err(|_| "Invalid payload")?;
+        let reply = match method.as_str() {
+            "Transfer" | "TransferFrom" => (service, method, self.transfers_succeed).encode(),
+            "GetVotesAt" => (service, method, self.votes).encode(),
+            _ => return Err("Unknown method"),
+        };
+        Ok(Some(reply))
+    }
+
+    fn handle_reply(&mut self, _payload: Vec<u8>) -> Result<(), &'static str> {
+        Ok(())
+    }
+
+    fn handle_signal(&mut self, _payload: Vec<u8>) -> Result<(), &'static str> {
+        Ok(())
+    }
+
+    fn state(&mut self) -> Result<Vec<u8>, &'static str> {
+        Ok(Vec::new())
+    }
+}
+
+// NFT program reporting `tokens` NFTs for every owner
+#[derive(Debug, Clone)]
+struct MockVnft {
+    tokens: u32,
+}
+
+impl WasmProgram for MockVnft {
+    fn init(&mut self, _payload: Vec<u8>) -> Result<Option<Vec<u8>>, &'static str> {
+        Ok(None)
+    }
+
+    fn handle(&mut self, payload: Vec<u8>) -> Result<Option<Vec<u8>>, &'static str> {
+        let (service, method) =
+            <(String, String)>::decode(&mut payload.as_slice()).map_err(|_| "Invalid payload")?;
+        if method != "TokensForOwner" {
+            return Err("Unknown method");
+        }
+        let tokens: Vec<(U256, TokenMetadata)> = (0..self.tokens)
+            .map(|token_id| {
+                let metadata = TokenMetadata {
+                    name: String::new(),
+                    description: String::new(),
+                    media: String::new(),
+                    reference: String::new(),
+                };
+                (U256::from(token_id), metadata)
+            })
+            .collect();
+        Ok(Some((service, method, tokens).encode()))
+    }
+
+    fn handle_reply(&mut self, _payload: Vec<u8>) -> Result<(), &'static str> {
+        Ok(())
+    }
+
+    fn handle_signal(&mut self, _payload: Vec<u8>) -> Result<(), &'static str> {
+        Ok(())
+    }
+
+    fn state(&mut self) -> Result<Vec<u8>, &'static str> {
+        Ok(Vec::new())
+    }
+}
+
+// Target of an executed ballot that only accepts `Vft/Mint`
+#[derive(Debug, Clone)]
+struct MockTarget;
+
+impl WasmProgram for MockTarget {
+    fn init(&mut self, _payload: Vec<u8>) -> Result<Option<Vec<u8>>, &'static str> {
+        Ok(None)
+    }
+
+    fn handle(&mut self, payload: Vec<u8>) -> Result<Option<Vec<u8>>, &'static str> {
+        let mut input = payload.as_slice();
+        let (service, method) =
+            <(String, String)>::decode(&mut input).map_err(|_| "Invalid payload")?;
+        if service != "Vft" || method != "Mint" {
+            return Err("Unknown method");
+        }
+        <(ActorId, U256)>::decode(&mut input).map_err(|_| "Invalid arguments")?;
+        Ok(Some((service, method, true).encode()))
+    }
+
+    fn handle_reply(&mut self, _payload: Vec<u8>) -> Result<(), &'static str> {
+        Ok(())
+    }
+
+    fn handle_signal(&mut self, _payload: Vec<u8>) -> Result<(), &'static str> {
+        Ok(())
+    }
+
+    fn state(&mut self) -> Result<Vec<u8>, &'static str> {
+        Ok(Vec::new())
+    }
+}
+
+fn mock(system: &System, id: u64, program: impl WasmProgram + 'static) -> Program<'_> {
+    let mock = Program::mock_with_id(system, id, program);
+    let message_id = mock.send_bytes(ADMIN, b"init");
+    assert!(system.run_next_block().succeed.contains(&message_id));
+    mock
+}
+
+#[test]
+fn multisig_executes_once_threshold_is_reached() {
+    let system = system();
+    let program = poll(&system);
+
+    let admins = vec![actor(ADMIN), actor(ALICE), actor(BOB)];
+    let event = send(&system, &program, ADMIN, "SetMultisig", (admins, 2u32));
+    assert!(matches!(event, Events::MultisigEnabled { threshold: 2 }));
+
+    // Below the threshold the action only waits for more approvals
+    let event = send(&system, &program, ALICE, "ProposeClose", ());
+    assert!(matches!(
+        event,
+        Events::ActionApproved {
+            action: AdminAction::CloseVoting,
+            approvals: 1
+        }
+    ));
+    assert!(call::<bool>(
+        &system,
+        &program,
+        ADMIN,
+        "QueryVotingOpen",
+        ()
+    ));
+    let pending: Vec<PendingAction> = call(&system, &program, ADMIN, "QueryPendingActions", ());
+    assert_eq!(pending.len(), 1);
+    assert_error(
+        send(&system, &program, ALICE, "ProposeClose", ()),
+        "Already approved",
+    );
+
+    let event = send(&system, &program, BOB, "ProposeClose", ());
+    assert!(matches!(event, Events::VotingClosed));
+    assert!(!call::<bool>(
+        &system,
+        &program,
+        ADMIN,
+        "QueryVotingOpen",
+        ()
+    ));
+    let pending: Vec<PendingAction> = call(&system, &program, ADMIN, "QueryPendingActions", ());
+    assert!(pending.is_empty());
+}
+
+#[test]
+fn multisig_routes_every_admin_action_through_proposals() {
+    let system = system();
+    let program = poll(&system);
+
+    send(&system, &program, ADMIN, "GrantOptionManager", actor(ADMIN));
+    send(&system, &program, ADMIN, "AddOption", "C".to_string());
+    send(
+        &system,
+        &program,
+        ADMIN,
+        "SetMultisig",
+        (vec![actor(ALICE), actor(BOB)], 2u32),
+    );
+
+    let error = "Multi-sig is enabled, use proposals";
+    assert_error(
+        send(&system, &program, ADMIN, "RemoveOption", "C".to_string()),
+        error,
+    );
+    assert_error(
+        send(
+            &system,
+            &program,
+            ADMIN,
+            "SetCredits",
+            (actor(ALICE), 10u64),
+        ),
+        error,
+    );
+    assert_error(
+        send(&system, &program, ADMIN, "GrantCloser", actor(CAROL)),
+        error,
+    );
+    assert_error(
+        send(&system, &program, CAROL, "ProposeClose", ()),
+        "Only multi-sig admins can propose",
+    );
+
+    let action = AdminAction::RemoveOption("C".to_string());
+    send(&system, &program, ALICE, "ProposeAction", action.clone());
+    let event = send(&system, &program, BOB, "ProposeAction", action);
+    assert!(matches!(event, Events::OptionRemoved(ref option) if option == "C"));
+    let current: Vec<String> = call(&system, &program, ADMIN, "QueryOptions", ());
+    assert_eq!(current, options(&["A", "B"]));
+}
+
+#[test]
+fn multisig_rejects_invalid_admin_sets() {
+    let system = system();
+    let program = poll(&system);
+
+    assert_error(
+        send(
+            &system,
+            &program,
+            ADMIN,
+            "SetMultisig",
+            (vec![actor(ALICE)], 2u32),
+        ),
+        "Invalid threshold",
+    );
+    let duplicates = vec![actor(ALICE), actor(ALICE)];
+    assert_error(
+        send(&system, &program, ADMIN, "SetMultisig", (duplicates, 1u32)),
+        "Duplicate admins are not allowed",
+    );
+    let too_many: Vec<ActorId> = (0..33u64).map(|id| actor(1_000 + id)).collect();
+    assert_error(
+        send(&system, &program, ADMIN, "SetMultisig", (too_many, 1u32)),
+        "Too many admins",
+    );
+    assert_error(
+        send(
+            &system,
+            &program,
+            ALICE,
+            "SetMultisig",
+            (vec![actor(ALICE)], 1u32),
+        ),
+        "Only admin can set multi-sig",
+    );
+}
+
+#[test]
+fn allow_list_restricts_voting() {
+    let system = system();
+    let no_indexer: Option<ActorId> = None;
+    let program = deploy(
+        &system,
+        "New",
+        (
+            actor(ADMIN),
+            options(&["A", "B"]),
+            Some(vec![actor(ALICE)]),
+            no_indexer,
+        ),
+    );
+
+    assert!(call::<bool>(
+        &system,
+        &program,
+        ADMIN,
+        "QueryIsEligible",
+        actor(ALICE)
+    ));
+    assert!(!call::<bool>(
+        &system,
+        &program,
+        ADMIN,
+        "QueryIsEligible",
+        actor(BOB)
+    ));
+    assert!(matches!(
+        send(&system, &program, ALICE, "Vote", "A".to_string()),
+        Events::VoteCast { .. }
+    ));
+    assert_error(
+        send(&system, &program, BOB, "Vote", "A".to_string()),
+        "Not eligible",
+    );
+    assert_eq!(
+        results(&system, &program),
+        vec![("A".to_string(), 1), ("B".to_string(), 0)]
+    );
+}
+
+#[test]
+fn voting_is_open_to_all_by_default() {
+    let system = system();
+    let program = poll(&system);
+
+    assert!(call::<bool>(
+        &system,
+        &program,
+        ADMIN,
+        "QueryIsEligible",
+        actor(BOB)
+    ));
+    assert!(matches!(
+        send(&system, &program, BOB, "Vote", "B".to_string()),
+        Events::VoteCast { .. }
+    ));
+    assert_error(
+        send(&system, &program, BOB, "Vote", "B".to_string()),
+        "Already voted",
+    );
+}
+
+#[test]
+fn winning_stakes_share_the_losing_pool() {
+    let system = system();
+    let program = poll(&system);
+    let token = mock(
+        &system,
+        VFT_ID,
+        MockVft {
+            votes: U256::zero(),
+            transfers_succeed: true,
+        },
+    );
+
+    send(&system, &program, ADMIN, "SetStakeToken", token.id());
+    let event = send(
+        &system,
+        &program,
+        ALICE,
+        "VoteAndStake",
+        ("A".to_string(), U256::from(100)),
+    );
+    assert!(matches!(event, Events::VoteCast { .. }));
+    send(
+        &system,
+        &program,
+        BOB,
+        "VoteAndStake",
+        ("A".to_string(), U256::from(50)),
+    );
+    send(
+        &system,
+        &program,
+        CAROL,
+        "VoteAndStake",
+        ("B".to_string(), U256::from(30)),
+    );
+    assert_error(
+        send(&system, &program, ALICE, "ClaimStake", ()),
+        "Voting is still open",
+    );
+    send(&system, &program, ADMIN, "GrantCloser", actor(ADMIN));
+    send(&system, &program, ADMIN, "CloseVoting", ());
+
+    // Alice staked two thirds of the winning side and gets two thirds of the pool
+    let event = send(&system, &program, ALICE, "ClaimStake", ());
+    assert!(matches!(event, Events::StakeClaimed { amount, .. } if amount == U256::from(120)));
+    assert_error(
+        send(&system, &program, ALICE, "ClaimStake", ()),
+        "No stake to claim",
+    );
+    assert_error(
+        send(&system, &program, CAROL, "ClaimStake", ()),
+        "Stake forfeited",
+    );
+    let event = send(&system, &program, CAROL, "ClaimStakeFor", actor(BOB));
+    assert!(matches!(event, Events::StakeClaimed { amount, .. } if amount == U256::from(60)));
+}
+
+#[test]
+fn failed_stake_transfer_reverts_the_vote() {
+    let system = system();
+    let program = poll(&system);
+    let token = mock(
+        &system,
+        VFT_ID,
+        MockVft {
+            votes: U256::zero(),
+            transfers_succeed: false,
+        },
+    );
+
+    assert_error(
+        send(
+            &system,
+            &program,
+            ALICE,
+            "VoteAndStake",
+            ("A".to_string(), U256::from(10)),
+        ),
+        "Staking is not configured",
+    );
+    send(&system, &program, ADMIN, "SetStakeToken", token.id());
+    assert_error(
+        send(
+            &system,
+            &program,
+            ALICE,
+            "VoteAndStake",
+            ("A".to_string(), U256::zero()),
+        ),
+        "Stake cannot be zero",
+    );
+    assert_error(
+        send(
+            &system,
+            &program,
+            ALICE,
+            "VoteAndStake",
+            ("A".to_string(), U256::from(10)),
+        ),
+        "Stake transfer failed",
+    );
+
+    assert_eq!(
+        results(&system, &program),
+        vec![("A".to_string(), 0), ("B".to_string(), 0)]
+    );
+    let stake: Option<(Option<String>, U256)> =
+        call(&system, &program, ADMIN, "QueryStake", actor(ALICE));
+    assert!(stake.is_none());
+    assert!(matches!(
+        send(&system, &program, ALICE, "Vote", "A".to_string()),
+        Events::VoteCast { .. }
+    ));
+}
+
+#[test]
+fn reset_archives_and_clears_the_poll() {
+    let system = system();
+    let program = poll(&system);
+
+    send(&system, &program, ALICE, "Vote", "A".to_string());
+    assert_error(
+        send(&system, &program, ADMIN, "Reset", options(&["X", "Y"])),
+        "Voting must be closed",
+    );
+    send(&system, &program, ADMIN, "GrantCloser", actor(ADMIN));
+    send(&system, &program, ADMIN, "CloseVoting", ());
+    assert_error(
+        send(&system, &program, ALICE, "Reset", options(&["X", "Y"])),
+        "Only admin can reset voting",
+    );
+
+    let event = send(&system, &program, ADMIN, "Reset", options(&["X", "Y"]));
+    assert!(matches!(event, Events::VotingReset { round: 1 }));
+    assert_eq!(
+        results(&system, &program),
+        vec![("X".to_string(), 0), ("Y".to_string(), 0)]
+    );
+    let archive: Vec<ArchivedPoll> = call(&system, &program, ADMIN, "QueryArchive", ());
+    assert_eq!(archive.len(), 1);
+    assert!(archive[0].results.contains(&("A".to_string(), 1)));
+
+    // The previous voters can vote again in the new round
+    assert!(matches!(
+        send(&system, &program, ALICE, "Vote", "X".to_string()),
+        Events::VoteCast { .. }
+    ));
+}
+
+#[test]
+fn quadratic_votes_spend_credits_across_options() {
+    let system = system();
+    let program = poll(&system);
+
+    send(&system, &program, ADMIN, "SetQuadratic", true);
+    send(
+        &system,
+        &program,
+        ADMIN,
+        "SetCredits",
+        (actor(ALICE), 14u64),
+    );
+    assert_error(
+        send(&system, &program, ALICE, "Vote", "A".to_string()),
+        "Poll uses quadratic voting",
+    );
+
+    let event = send(
+        &system,
+        &program,
+        ALICE,
+        "VoteQuadratic",
+        ("A".to_string(), 2u64),
+    );
+    assert!(matches!(
+        event,
+        Events::QuadraticVoteCast {
+            votes: 2,
+            cost: 4,
+            ..
+        }
+    ));
+    // A third vote on A costs 3^2 - 2^2 = 5
+    let event = send(
+        &system,
+        &program,
+        ALICE,
+        "VoteQuadratic",
+        ("A".to_string(), 1u64),
+    );
+    assert!(matches!(
+        event,
+        Events::QuadraticVoteCast {
+            votes: 1,
+            cost: 5,
+            ..
+        }
+    ));
+    let event = send(
+        &system,
+        &program,
+        ALICE,
+        "VoteQuadratic",
+        ("B".to_string(), 2u64),
+    );
+    assert!(matches!(
+        event,
+        Events::QuadraticVoteCast {
+            votes: 2,
+            cost: 4,
+            ..
+        }
+    ));
+    assert_eq!(
+        call::<u64>(&system, &program, ADMIN, "QueryCredits", actor(ALICE)),
+        1
+    );
+
+    assert_error(
+        send(
+            &system,
+            &program,
+            ALICE,
+            "VoteQuadratic",
+            ("B".to_string(), 1u64),
+        ),
+        "Insufficient credits",
+    );
+    assert_error(
+        send(
+            &system,
+            &program,
+            BOB,
+            "VoteQuadratic",
+            ("A".to_string(), 1u64),
+        ),
+        "Insufficient credits",
+    );
+    assert_eq!(
+        results(&system, &program),
+        vec![("A".to_string(), 3), ("B".to_string(), 2)]
+    );
+}
+
+#[test]
+fn write_ins_wait_for_approval() {
+    let system = system();
+    let program = poll(&system);
+
+    assert_error(
+        send(&system, &program, ALICE, "WriteIn", "C".to_string()),
+        "Write-ins are disabled",
+    );
+    send(&system, &program, ADMIN, "SetAllowWriteIn", true);
+    let event = send(&system, &program, ALICE, "WriteIn", "C".to_string());
+    assert!(matches!(event, Events::WriteInSuggested { ref option, .. } if option == "C"));
+    assert_error(
+        send(&system, &program, BOB, "WriteIn", "C".to_string()),
+        "Already suggested",
+    );
+    assert_error(
+        send(&system, &program, BOB, "Vote", "C".to_string()),
+        "Invalid option",
+    );
+
+    assert_error(
+        send(&system, &program, ADMIN, "ApproveWriteIn", "C".to_string()),
+        "Only option managers can approve write-ins",
+    );
+    send(&system, &program, ADMIN, "GrantOptionManager", actor(ADMIN));
+    let event = send(&system, &program, ADMIN, "ApproveWriteIn", "C".to_string());
+    assert!(matches!(event, Events::OptionAdded(ref option) if option == "C"));
+    let pending: Vec<(String, ActorId)> = call(&system, &program, ADMIN, "QueryPendingOptions", ());
+    assert!(pending.is_empty());
+    assert!(matches!(
+        send(&system, &program, BOB, "Vote", "C".to_string()),
+        Events::VoteCast { .. }
+    ));
+}
+
+#[test]
+fn write_ins_are_bounded() {
+    let system = system();
+    let program = poll(&system);
+    send(&system, &program, ADMIN, "SetAllowWriteIn", true);
+
+    let too_long = "x".repeat(MAX_WRITE_IN_LENGTH + 1);
+    assert_error(
+        send(&system, &program, ALICE, "WriteIn", too_long),
+        "Option is too long",
+    );
+    for index in 0..MAX_PENDING_WRITE_INS {
+        let event = send(
+            &system,
+            &program,
+            ALICE,
+            "WriteIn",
+            format!("Option {}", index),
+        );
+        assert!(matches!(event, Events::WriteInSuggested { .. }));
+    }
+    assert_error(
+        send(&system, &program, BOB, "WriteIn", "One more".to_string()),
+        "Too many pending write-ins",
+    );
+
+    // Approving a suggestion makes room for another
+    send(&system, &program, ADMIN, "GrantOptionManager", actor(ADMIN));
+    send(
+        &system,
+        &program,
+        ADMIN,
+        "ApproveWriteIn",
+        "Option 0".to_string(),
+    );
+    let event = send(&system, &program, BOB, "WriteIn", "One more".to_string());
+    assert!(matches!(event, Events::WriteInSuggested { .. }));
+}
+
+#[test]
+fn passing_ballot_executes_its_call_once() {
+    let system = system();
+    let no_eligible: Option<Vec<ActorId>> = None;
+    let no_indexer: Option<ActorId> = None;
+    let program = deploy(
+        &system,
+        "NewBinary",
+        (actor(ADMIN), 5_000u16, false, no_eligible, no_indexer),
+    );
+    let target = mock(&system, TARGET_ID, MockTarget);
+
+    let payload = ExecutionPayload {
+        target: target.id(),
+        method: "Vft/Mint".to_string(),
+        args: (actor(ALICE), U256::from(1_000)).encode(),
+    };
+    send(&system, &program, ADMIN, "SetExecution", payload);
+    send(&system, &program, ALICE, "Vote", "Yes".to_string());
+    assert_error(
+        send(&system, &program, ALICE, "Execute", ()),
+        "Ballot did not pass",
+    );
+    send(&system, &program, ADMIN, "GrantCloser", actor(ADMIN));
+    send(&system, &program, ADMIN, "CloseVoting", ());
+
+    let event = send(&system, &program, BOB, "Execute", ());
+    assert!(matches!(event, Events::Executed { ref method, .. } if method == "Vft/Mint"));
+    assert_error(
+        send(&system, &program, BOB, "Execute", ()),
+        "Already executed",
+    );
+}
+
+#[test]
+fn failed_execution_can_be_retried() {
+    let system = system();
+    let no_eligible: Option<Vec<ActorId>> = None;
+    let no_indexer: Option<ActorId> = None;
+    let program = deploy(
+        &system,
+        "NewBinary",
+        (actor(ADMIN), 5_000u16, false, no_eligible, no_indexer),
+    );
+    let target = mock(&system, TARGET_ID, MockTarget);
+
+    assert_error(
+        send(&system, &program, ALICE, "Execute", ()),
+        "Nothing to execute",
+    );
+    let payload = ExecutionPayload {
+        target: target.id(),
+        method: "Vft/Burn".to_string(),
+        args: Vec::new(),
+    };
+    send(&system, &program, ADMIN, "SetExecution", payload);
+    send(&system, &program, ALICE, "Vote", "Yes".to_string());
+    send(&system, &program, ADMIN, "GrantCloser", actor(ADMIN));
+    send(&system, &program, ADMIN, "CloseVoting", ());
+
+    assert_error(
+        send(&system, &program, BOB, "Execute", ()),
+        "Execution failed",
+    );
+    let (_, executed): (Option<ExecutionPayload>, bool) =
+        call(&system, &program, ADMIN, "QueryExecution", ());
+    assert!(!executed);
+}
+
+#[test]
+fn weighted_votes_combine_tokens_and_nfts() {
+    let system = system();
+    let program = poll(&system);
+    let vft = mock(
+        &system,
+        VFT_ID,
+        MockVft {
+            votes: U256::from(5),
+            transfers_succeed: true,
+        },
+    );
+    let vnft = mock(&system, VNFT_ID, MockVnft { tokens: 2 });
+
+    send(
+        &system,
+        &program,
+        ADMIN,
+        "SetVotingPower",
+        (Some(vft.id()), Some(vnft.id()), 10u64),
+    );
+    assert!(matches!(
+        send(&system, &program, ALICE, "Vote", "A".to_string()),
+        Events::VoteCast { .. }
+    ));
+    assert_eq!(
+        results(&system, &program),
+        vec![("A".to_string(), 25), ("B".to_string(), 0)]
+    );
+
+    // The same NFTs show up for Bob, but each NFT counts once per poll
+    send(&system, &program, BOB, "Vote", "B".to_string());
+    assert_eq!(
+        results(&system, &program),
+        vec![("A".to_string(), 25), ("B".to_string(), 5)]
+    );
+}
+
+#[test]
+fn voters_without_power_are_rejected() {
+    let system = system();
+    let program = poll(&system);
+    let vft = mock(
+        &system,
+        VFT_ID,
+        MockVft {
+            votes: U256::zero(),
+            transfers_succeed: true,
+        },
+    );
+    let vnft = mock(&system, VNFT_ID, MockVnft { tokens: 0 });
+
+    send(
+        &system,
+        &program,
+        ADMIN,
+        "SetVotingPower",
+        (Some(vft.id()), Some(vnft.id()), 10u64),
+    );
+    assert_error(
+        send(&system, &program, ALICE, "Vote", "A".to_string()),
+        "No voting power",
+    );
+    assert_eq!(
+        results(&system, &program),
+        vec![("A".to_string(), 0), ("B".to_string(), 0)]
+    );
+}
@@ -10,8 +10,14 @@ pub struct Program(());
 
 #[program]
 impl Program {
-    pub fn new(name: String, symbol: String, decimals: u8) -> Self {
-        ExtendedService::seed(name, symbol, decimals);
+    pub fn new(
+        name: String,
+        symbol: String,
+        decimals: u8,
+        max_supply: Option<U256>,
+        import_open: bool,
+    ) -> Self {
+        ExtendedService::seed(name, symbol, decimals, max_supply, import_open);
         Self(())
     }
 
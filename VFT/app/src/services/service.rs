@@ -2,11 +2,17 @@
 
 #![allow(static_mut_refs)]
 
+use core::ops::Bound;
 use sails_rs::{
-    gstd::msg,
-    collections::HashSet,
+    gstd::{exec, msg},
+    collections::{HashMap, HashSet},
     prelude::*,
+    calls::ActionIo,
 };
+use extended_vft_client::vft::io as vft_io;
+use schnorrkel::{PublicKey, Signature};
+use pagination::paginate;
+use sha3::{Digest, Keccak256};
 use vft_service::utils;
 use vft_service::{Service as VftService, Storage};
 use vft_service::{
@@ -16,19 +22,624 @@ use vft_service::{
 
 #[derive(Default)]
 pub struct ExtendedStorage {
+    name: Option<String>,
+    symbol: Option<String>,
+    icon_uri: Option<String>,
+    project_url: Option<String>,
+    description: Option<String>,
     minters: HashSet<ActorId>,
+    minter_expiry: HashMap<ActorId, u64>,
+    minter_stats: HashMap<ActorId, MinterStats>,
+    processed_mint_ids: HashSet<H256>,
     burners: HashSet<ActorId>,
     admins: HashSet<ActorId>,
+    total_minted: U256,
+    total_burned: U256,
+    burned_by: HashMap<ActorId, U256>,
+    permit_nonces: HashMap<ActorId, u64>,
+    allowance_expiry: HashMap<(ActorId, ActorId), u64>,
+    paused: bool,
+    burn_circuit_breaker: Option<(u16, u64)>,
+    burn_window_start: u64,
+    burned_in_window: U256,
+    max_supply: Option<U256>,
+    blacklist: HashSet<ActorId>,
+    whitelist_only: bool,
+    soulbound: bool,
+    whitelist: HashSet<ActorId>,
+    transfer_fee_bps: u16,
+    fee_collector: Option<ActorId>,
+    fee_exempt: HashSet<ActorId>,
+    non_circulating: HashSet<ActorId>,
+    auto_burn_bps: u16,
+    auto_burned: U256,
+    flash_fee_bps: u16,
+    flash_borrowers: HashSet<ActorId>,
+    balance_index: BTreeSet<(U256, ActorId)>,
+    holder_accounts: BTreeSet<ActorId>,
+    dividends: Vec<Dividend>,
+    dividends_claimed: HashSet<(u32, ActorId)>,
+    next_stream_id: u64,
+    streams: HashMap<u64, Stream>,
+    next_lock_id: u64,
+    locks: HashMap<u64, TimeLock>,
+    locks_cancelable: bool,
+    frozen: HashMap<ActorId, U256>,
+    recoveries: HashMap<ActorId, Recovery>,
+    rebase_index: U256,
+    interest_rate_ppm: u32,
+    last_accrual_block: u32,
+    supply_history: Vec<SupplyChange>,
+    supply_history_next: usize,
+    account_history: HashMap<ActorId, Vec<AccountOperation>>,
+    burn_log: Vec<BurnRecord>,
+    burn_log_next: usize,
+    compliance_log: Vec<ComplianceRecord>,
+    import_open: bool,
+    bridges: HashSet<ActorId>,
+    compliance: HashSet<ActorId>,
+    guardians: HashSet<ActorId>,
+    tiers: HashMap<ActorId, u8>,
+    tier_rules: HashMap<u8, TierRule>,
+    tier_spent: HashMap<ActorId, (u32, U256)>,
+    bridge_out_nonce: u64,
+    bridge_in_nonces: HashSet<u64>,
+    spending_limits: HashMap<ActorId, SpendingLimit>,
+    transfer_hooks: TransferHooks,
+    mint_multisig: Option<(U256, u32)>,
+    threshold_period_start: u64,
+    minted_in_threshold_period: U256,
+    next_proposal_id: u64,
+    proposals: HashMap<u64, MultisigProposal>,
+    emission: Option<EmissionSchedule>,
+    faucet: Option<FaucetConfig>,
+    faucet_last_claim: HashMap<ActorId, u32>,
+    faucet_day_start: u64,
+    faucet_minted_today: U256,
+    next_scheduled_mint_id: u64,
+    scheduled_mints: HashMap<u64, ScheduledMint>,
+    pending_admin: Option<ActorId>,
+    snapshot_id: u64,
+    snapshot_indexes: Vec<U256>,
+    account_snapshots: HashMap<ActorId, Vec<(u64, U256)>>,
+    supply_snapshots: Vec<(u64, U256)>,
+    delegates: HashMap<ActorId, ActorId>,
+    vote_checkpoints: HashMap<ActorId, Vec<(u32, U256)>>,
+    vesting: HashMap<ActorId, Vec<VestingSchedule>>,
+    airdrop_root: Option<[u8; 32]>,
+    airdrop_total: U256,
+    airdrop_claimed: U256,
+    airdrop_bitmap: HashMap<u32, u64>,
+    token_accounts: HashSet<ActorId>,
+    wrapped: HashMap<ActorId, U256>,
+}
+
+/// Balances of the accounts touched by a mutation and the total supply before it,
+/// handed to `after_balance_change` once the mutation is done.
+struct PriorBalances {
+    balances: Vec<(ActorId, U256)>,
+    total_supply: U256,
 }
 
 static mut EXTENDED_STORAGE: Option<ExtendedStorage> = None;
 
+/// Maximum number of entries returned by a paginated query.
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// Maximum number of accounts `sweep_dust` processes per call.
+pub const MAX_DUST_SWEEP: usize = 50;
+
+/// Maximum number of allowances `prune_expired_allowances` removes per call.
+pub const MAX_ALLOWANCE_PRUNE: usize = 50;
+
+/// Fixed-point scale of the rebase index; an index of `REBASE_PRECISION` means 1 token per share.
+pub const REBASE_PRECISION: u64 = 1_000_000_000_000_000_000;
+
+/// Number of supply changes kept by `supply_history`; older ones are overwritten.
+pub const MAX_SUPPLY_HISTORY: usize = 1_000;
+
+/// Number of mints and burns kept per account by `account_history`; older ones are dropped.
+pub const MAX_ACCOUNT_HISTORY: usize = 100;
+
+/// Number of burns kept by `burn_log`; older ones are overwritten.
+pub const MAX_BURN_LOG: usize = 1_000;
+
+/// Maximum length in bytes of the token name, symbol and icon URI.
+pub const MAX_METADATA_LENGTH: usize = 256;
+
+/// Maximum length in bytes of the token description.
+pub const MAX_DESCRIPTION_LENGTH: usize = 1_024;
+
+/// Maximum length in bytes of a burn or compliance reason.
+pub const MAX_REASON_LENGTH: usize = 128;
+
+/// Maximum length in bytes of a transfer memo.
+pub const MAX_MEMO_LENGTH: usize = 256;
+
+/// Time in ms a lost account has to veto a recovery before it can be executed.
+pub const RECOVERY_DELAY_MS: u64 = 7 * 86_400_000;
+
+/// Length in ms of the window the faucet's daily cap applies to.
+pub const FAUCET_DAY_MS: u64 = 86_400_000;
+
+/// Length in ms of the window over which mints add up against the mint multisig threshold.
+pub const MINT_THRESHOLD_PERIOD_MS: u64 = 86_400_000;
+
+/// Gas attached to the delayed message executing a scheduled mint.
+pub const SCHEDULED_MINT_GAS: u64 = 10_000_000_000;
+
 #[derive(Encode, Decode, TypeInfo)]
 #[codec(crate = sails_rs::scale_codec)]
 #[scale_info(crate = sails_rs::scale_info)]
 pub enum Event {
-    Minted { to: ActorId, value: U256 },
-    Burned { from: ActorId, value: U256 },
+    Minted { to: ActorId, value: U256, balance: U256, total_supply: U256 },
+    Burned { from: ActorId, value: U256, balance: U256, total_supply: U256 },
+    MintedBatch { recipients: u32, total: U256 },
+    MintedLocked { to: ActorId, value: U256, cliff: u64, end: u64 },
+    Released { account: ActorId, value: U256 },
+    AirdropSet { root: [u8; 32], total: U256 },
+    AirdropClaimed { account: ActorId, index: u32, amount: U256 },
+    Deposited { account: ActorId, value: U256 },
+    Withdrawn { account: ActorId, value: U256 },
+    BurnedWithReason { from: ActorId, value: U256, reason: String },
+    BurnedBatch { accounts: u32, total: U256 },
+    Swept { to: ActorId, sources: u32, total: U256 },
+    DustSwept { to: ActorId, accounts: u32, total: U256 },
+    AllowancesPruned { count: u32 },
+    Permit { owner: ActorId, spender: ActorId, value: U256, nonce: u64 },
+    FlashFeeSet { bps: u16 },
+    FlashMinted { borrower: ActorId, amount: U256, fee: U256 },
+    FlashMintDefaulted { borrower: ActorId, amount: U256, recovered: U256 },
+    TransferredWithMemo { from: ActorId, to: ActorId, value: U256, memo: String },
+    TransferredBatch { from: ActorId, recipients: u32, total: U256 },
+    TransferCallRefunded { from: ActorId, to: ActorId, received: U256, refunded: U256 },
+    TransferredAndCalled { from: ActorId, to: ActorId, value: U256 },
+    ApprovedAndCalled { owner: ActorId, spender: ActorId, value: U256 },
+    TransferBySig { from: ActorId, to: ActorId, value: U256, nonce: u64, relayer: ActorId },
+    TokenBoundTransfer { nft_program: ActorId, token_id: U256, to: ActorId, value: U256 },
+    CircuitBreakerTripped { burned: U256, window_ms: u64 },
+    Rebased { delta_ppm: i32, index: U256 },
+    InterestRateSet { ppm_per_block: u32 },
+    Snapshot { id: u64 },
+    TransferLocked { id: u64, from: ActorId, to: ActorId, value: U256, release_at: u64 },
+    LockClaimed { id: u64 },
+    LockCancelled { id: u64 },
+    LocksCancelableSet { cancelable: bool },
+    StreamCreated { id: u64, sender: ActorId, recipient: ActorId, rate_per_block: U256, end_block: u32 },
+    StreamWithdrawn { id: u64, value: U256 },
+    StreamCancelled { id: u64, paid: U256, refunded: U256 },
+    DividendDeposited { id: u32, snapshot_id: u64, amount: u128 },
+    DividendClaimed { account: ActorId, amount: u128 },
+    DelegateChanged { delegator: ActorId, from_delegate: Option<ActorId>, to_delegate: ActorId },
+    DelegateVotesChanged { delegate: ActorId, previous: U256, new: U256 },
+    MetadataUpdated { name: String, symbol: String, icon_uri: Option<String> },
+    ExtendedMetadataUpdated { metadata: ExtendedMetadata },
+    Paused,
+    Unpaused,
+    MinterGrantedUntil { account: ActorId, deadline: u64 },
+    RoleGranted { role: Role, account: ActorId, by: ActorId },
+    RoleRevoked { role: Role, account: ActorId, by: ActorId },
+    AdminProposed { candidate: ActorId },
+    AdminAccepted { admin: ActorId },
+    AdminRenounced { admin: ActorId, locked: bool },
+    TransferHooksSet { hooks: TransferHooks },
+    SpendingLimitSet { account: ActorId, limit: Option<(U256, u32)> },
+    TierSet { account: ActorId, tier: u8 },
+    TierRuleSet { tier: u8, rule: Option<TierRule> },
+    Frozen { account: ActorId, amount: U256, by: ActorId, reason: String },
+    Unfrozen { account: ActorId, amount: U256, by: ActorId, reason: String },
+    BridgeBurned { nonce: u64, from: ActorId, value: U256, dest_chain: String, dest_address: Vec<u8> },
+    BridgeMinted { nonce: u64, to: ActorId, value: U256 },
+    TokensRescued { token_program: ActorId, to: ActorId, value: U256 },
+    StateImported { entries: u32 },
+    ImportFinished,
+    RecoveryInitiated { lost: ActorId, new_owner: ActorId, executable_at: u64, by: ActorId },
+    RecoveryCancelled { lost: ActorId, by: ActorId },
+    RecoveryExecuted { lost: ActorId, new_owner: ActorId, value: U256 },
+    Seized { from: ActorId, to: ActorId, value: U256, by: ActorId, reason: String },
+    MintMultisigSet { config: Option<(U256, u32)> },
+    Proposed { id: u64, proposer: ActorId, action: MultisigAction },
+    ProposalApproved { id: u64, admin: ActorId, approvals: u32 },
+    ProposalCancelled { id: u64 },
+    EmissionScheduleSet { schedule: Option<EmissionSchedule> },
+    EmissionDripped { to: ActorId, value: U256, up_to_block: u32 },
+    FaucetSet { config: Option<FaucetConfig> },
+    FaucetDripped { to: ActorId, value: U256 },
+    MintScheduled { id: u64, to: ActorId, value: U256, execute_at: u32 },
+    ScheduledMintExecuted { id: u64 },
+    ScheduledMintFailed { id: u64, reason: String },
+    ScheduledMintCancelled { id: u64 },
+    Blacklisted { account: ActorId, by: ActorId, reason: String },
+    Unblacklisted { account: ActorId, by: ActorId, reason: String },
+    TransferFeeSet { bps: u16, collector: Option<ActorId> },
+    NonCirculatingAdded { account: ActorId },
+    NonCirculatingRemoved { account: ActorId },
+    FeeExemptionAdded { account: ActorId },
+    FeeExemptionRemoved { account: ActorId },
+    FeeCharged { from: ActorId, to: ActorId, gross: U256, net: U256, fee: U256 },
+    AutoBurnSet { bps: u16 },
+    AutoBurned { from: ActorId, value: U256 },
+    WhitelistOnlySet { enabled: bool },
+    SoulboundSet { enabled: bool },
+    Whitelisted { account: ActorId },
+    Unwhitelisted { account: ActorId },
+}
+
+#[derive(Encode, Decode, TypeInfo, Clone, Copy, PartialEq, Eq)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum Role {
+    Admin,
+    Minter,
+    Burner,
+    Bridge,
+    Compliance,
+    Guardian,
+    FlashBorrower,
+}
+
+/// One piece of state moved by `export_state` / `import_state`.
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum StateEntry {
+    RebaseIndex(U256),
+    Balance(ActorId, U256),
+    Allowance(ActorId, ActorId, U256),
+    AllowanceExpiry(ActorId, ActorId, u64),
+    Role(Role, ActorId),
+    Blacklisted(ActorId),
+    Frozen(ActorId, U256),
+    Vesting(ActorId, VestingSchedule),
+}
+
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct SupplySummary {
+    pub total_supply: U256,
+    pub holders: u32,
+    pub total_minted: U256,
+    pub total_burned: U256,
+}
+
+/// Message signed by the owner for `transfer_by_sig`, SCALE-encoded.
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct TransferMessage {
+    pub program: ActorId,
+    pub from: ActorId,
+    pub to: ActorId,
+    pub value: U256,
+    pub nonce: u64,
+    pub deadline: u64,
+}
+
+/// Shares minted locked, vesting linearly from `start` to `end` (block timestamps, ms)
+/// with nothing vested before `cliff`.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct VestingSchedule {
+    pub total: U256,
+    pub released: U256,
+    pub start: u64,
+    pub cliff: u64,
+    pub end: u64,
+}
+
+impl VestingSchedule {
+    /// Amount vested at `now`, released or not.
+    pub fn vested(&self, now: u64) -> U256 {
+        if now < self.cliff {
+            U256::zero()
+        } else if now >= self.end {
+            self.total
+        } else {
+            self.total.saturating_mul(U256::from(now - self.start)) / U256::from(self.end - self.start)
+        }
+    }
+}
+
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct Stats {
+    pub holders: u32,
+    pub total_supply: U256,
+    pub total_minted: U256,
+    pub total_burned: U256,
+    pub largest_balance: U256,
+}
+
+/// Native value deposited for holders, shared pro rata to balances at `snapshot_id`.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct Dividend {
+    pub snapshot_id: u64,
+    pub amount: u128,
+    pub claimed: u128,
+}
+
+/// Shares held by the program and paid out to `recipient` at `rate_per_block` shares from
+/// `start_block` until `end_block`.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct Stream {
+    pub sender: ActorId,
+    pub recipient: ActorId,
+    pub rate_per_block: U256,
+    pub start_block: u32,
+    pub end_block: u32,
+    pub withdrawn: U256,
+}
+
+impl Stream {
+    /// Amount streamed by `block`, withdrawn or not.
+    pub fn streamed(&self, block: u32) -> U256 {
+        let elapsed = block.min(self.end_block).saturating_sub(self.start_block);
+        self.rate_per_block.saturating_mul(U256::from(elapsed))
+    }
+
+    /// Amount the sender deposited for the whole stream.
+    pub fn deposit(&self) -> U256 {
+        self.streamed(self.end_block)
+    }
+}
+
+/// Shares held by the program until `release_at` (block timestamp, ms), then claimable by `to`.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct TimeLock {
+    pub from: ActorId,
+    pub to: ActorId,
+    pub value: U256,
+    pub release_at: u64,
+}
+
+/// A mint (`increase`) or burn of `amount` tokens by `actor`, leaving `new_total`.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct SupplyChange {
+    pub block: u32,
+    pub actor: ActorId,
+    pub increase: bool,
+    pub amount: U256,
+    pub new_total: U256,
+}
+
+/// What a minter minted so far with `mint` and `mint_batch`: the cumulative amount in
+/// tokens, the number of mints and the block of the latest one.
+#[derive(Encode, Decode, TypeInfo, Clone, Default)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct MinterStats {
+    pub minted: U256,
+    pub mints: u32,
+    pub last_mint_block: u32,
+}
+
+/// A mint (`minted`) or burn of `value` tokens on an account by `actor`, who held `role`
+/// at the time, if any.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct AccountOperation {
+    pub block: u32,
+    pub minted: bool,
+    pub actor: ActorId,
+    pub role: Option<Role>,
+    pub value: U256,
+}
+
+/// A `burn_with_reason` of `value` from `from` by `burner`.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct BurnRecord {
+    pub block: u32,
+    pub burner: ActorId,
+    pub from: ActorId,
+    pub value: U256,
+    pub reason: String,
+}
+
+/// Programs told about every user transfer, lock and stream payouts included: `before` is
+/// asked ahead of the transfer and, if `enforce` is set, must reply `true` for it to go
+/// through; `after` is notified once it's done.
+#[derive(Encode, Decode, TypeInfo, Clone, Default)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct TransferHooks {
+    pub before: Option<ActorId>,
+    pub after: Option<ActorId>,
+    pub enforce: bool,
+}
+
+/// Message sent to transfer hook programs, SCALE-encoded.
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct TransferHookMessage {
+    pub token: ActorId,
+    pub from: ActorId,
+    pub to: ActorId,
+    pub value: U256,
+    pub after: bool,
+}
+
+/// Pending move of a lost account's balance to `new_owner`, executable from `executable_at`
+/// (block timestamp, ms) unless vetoed.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct Recovery {
+    pub new_owner: ActorId,
+    pub initiated_by: ActorId,
+    pub executable_at: u64,
+}
+
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum ComplianceAction {
+    Blacklist,
+    Unblacklist,
+    Freeze(U256),
+    Unfreeze(U256),
+    Seize { to: ActorId, value: U256 },
+}
+
+/// A restrictive action taken on `account` by the admin `by`.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct ComplianceRecord {
+    pub block: u32,
+    pub action: ComplianceAction,
+    pub account: ActorId,
+    pub by: ActorId,
+    pub reason: String,
+}
+
+/// Cap on what an account can send per period of `period_blocks` blocks.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct SpendingLimit {
+    pub limit: U256,
+    pub period_blocks: u32,
+    pub period_start: u32,
+    pub spent: U256,
+}
+
+impl SpendingLimit {
+    /// Amount spent in the period containing `block`.
+    pub fn spent_at(&self, block: u32) -> U256 {
+        if block.saturating_sub(self.period_start) >= self.period_blocks {
+            U256::zero()
+        } else {
+            self.spent
+        }
+    }
+}
+
+/// Token details for wallets beyond name, symbol and decimals; the logo is the icon URI.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct ExtendedMetadata {
+    pub logo_uri: Option<String>,
+    pub project_url: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Restrictions on accounts of a KYC tier: at most `max_per_period` (amount, period in
+/// blocks) sent per period, and only to accounts of tier `min_recipient_tier` or above.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct TierRule {
+    pub max_per_period: Option<(U256, u32)>,
+    pub min_recipient_tier: u8,
+}
+
+/// Emission of `initial_rate` tokens per block to `recipient` from `start_block`, halved
+/// every `halving_interval` blocks; minted up to `dripped_until` so far.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct EmissionSchedule {
+    pub recipient: ActorId,
+    pub initial_rate: U256,
+    pub halving_interval: u32,
+    pub start_block: u32,
+    pub dripped_until: u32,
+}
+
+impl EmissionSchedule {
+    /// Amount emitted from block `from` until block `to`.
+    pub fn emitted(&self, from: u32, to: u32) -> U256 {
+        let interval = self.halving_interval as u64;
+        let mut total = U256::zero();
+        let mut block = from.max(self.start_block) as u64;
+        while block < to as u64 {
+            let halvings = (block - self.start_block as u64) / interval;
+            if halvings >= 256 {
+                break;
+            }
+            let rate = self.initial_rate >> halvings as usize;
+            if rate.is_zero() {
+                break;
+            }
+            let epoch_end = (self.start_block as u64 + (halvings + 1) * interval).min(to as u64);
+            total = total.saturating_add(rate.saturating_mul(U256::from(epoch_end - block)));
+            block = epoch_end;
+        }
+        total
+    }
+}
+
+/// Testnet faucet minting `amount` per call, at most once every `cooldown_blocks` per
+/// account and `daily_cap` in total per day.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct FaucetConfig {
+    pub amount: U256,
+    pub cooldown_blocks: u32,
+    pub daily_cap: U256,
+}
+
+/// Change made by a multisig proposal once enough admins approved it.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub enum MultisigAction {
+    Mint { to: ActorId, value: U256 },
+    SetMultisig(Option<(U256, u32)>),
+    AddAdmin(ActorId),
+    RemoveAdmin(ActorId),
+}
+
+/// Multisig action awaiting approval by admins.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct MultisigProposal {
+    pub proposer: ActorId,
+    pub action: MultisigAction,
+    pub approvals: Vec<ActorId>,
+}
+
+/// Mint of `value` to `to` scheduled by `scheduled_by`, executed by a delayed message
+/// at block `execute_at`.
+#[derive(Encode, Decode, TypeInfo, Clone)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct ScheduledMint {
+    pub scheduled_by: ActorId,
+    pub to: ActorId,
+    pub value: U256,
+    pub execute_at: u32,
+}
+
+/// Message signed by the owner for `permit`, SCALE-encoded.
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = sails_rs::scale_codec)]
+#[scale_info(crate = sails_rs::scale_info)]
+pub struct PermitMessage {
+    pub program: ActorId,
+    pub owner: ActorId,
+    pub spender: ActorId,
+    pub value: U256,
+    pub nonce: u64,
+    pub deadline: u64,
 }
 
 #[derive(Clone)]
@@ -38,13 +649,24 @@ pub struct ExtendedService {
 
 impl ExtendedService {
     /// Initialize storage with caller as admin, minter, and burner, and seed chain state for vft.
-    pub fn seed(name: String, symbol: String, decimals: u8) -> Self {
+    /// `max_supply` caps the total supply for fixed-supply tokens; `None` leaves it uncapped.
+    /// `import_open` allows `import_state` until `finish_import`, for migrated deployments.
+    pub fn seed(
+        name: String,
+        symbol: String,
+        decimals: u8,
+        max_supply: Option<U256>,
+        import_open: bool,
+    ) -> Self {
         let admin = msg::source();
         unsafe {
             EXTENDED_STORAGE = Some(ExtendedStorage {
                 admins: [admin].into(),
                 minters: [admin].into(),
                 burners: [admin].into(),
+                max_supply,
+                import_open,
+                ..Default::default()
             });
         };
         ExtendedService {
@@ -77,96 +699,3393 @@ impl ExtendedService {
         }
     }
 
-    /// Mint new tokens; must be allowed by minter.
-    pub fn mint(&mut self, to: ActorId, value: U256) -> bool {
-        // Only minters are allowed
-        if !self.get().minters.contains(&msg::source()) {
-            panic!("Not allowed to mint")
+    /// Approve `spender` permanently; replaces any previous deadline. Allowances are kept in
+    /// shares, so they follow rebases like balances do.
+    pub fn approve(&mut self, spender: ActorId, value: U256) -> bool {
+        self.get_mut().allowance_expiry.remove(&(msg::source(), spender));
+        self.vft.approve(spender, self.to_shares(value))
+    }
+
+    /// Approve the program `spender` and then send it `payload` so it can act on the
+    /// allowance right away. If the call fails, the approval is cleared.
+    pub async fn approve_and_call(&mut self, spender: ActorId, value: U256, payload: Vec<u8>) -> bool {
+        let owner = msg::source();
+        self.approve(spender, value);
+
+        let replied = match msg::send_bytes_for_reply(spender, payload, 0, 0) {
+            Ok(future) => future.await.is_ok(),
+            Err(_) => false,
         };
+        if !replied {
+            funcs::approve(Storage::allowances(), owner, spender, U256::zero());
+            return false;
+        }
 
-        let mutated = utils::panicking(|| {
-             mint(Storage::balances(), Storage::total_supply(), to, value)
-        });
-        if mutated {
-            self.emit_event(Event::Minted { to, value })
+        self.emit_event(Event::ApprovedAndCalled { owner, spender, value })
+            .expect("Notification Error");
+        true
+    }
+
+    /// Approve `spender` until `deadline` (block timestamp, ms); `None` never expires.
+    /// An expired allowance counts as zero.
+    pub fn approve_until(&mut self, spender: ActorId, value: U256, deadline: Option<u64>) -> bool {
+        let owner = msg::source();
+        let mutated = self.vft.approve(spender, self.to_shares(value));
+        match deadline {
+            Some(deadline) => self.get_mut().allowance_expiry.insert((owner, spender), deadline),
+            None => self.get_mut().allowance_expiry.remove(&(owner, spender)),
+        };
+        mutated
+    }
+
+    /// Approve `spender` until `deadline_ts` (block timestamp, ms).
+    pub fn approve_with_expiry(&mut self, spender: ActorId, value: U256, deadline_ts: u64) -> bool {
+        if deadline_ts <= exec::block_timestamp() {
+            panic!("Deadline passed")
+        };
+        self.approve_until(spender, value, Some(deadline_ts))
+    }
+
+    /// Remove up to `MAX_ALLOWANCE_PRUNE` expired allowances; open to anyone, as expired
+    /// allowances already count as zero. Returns whether expired allowances remain.
+    pub fn prune_expired_allowances(&mut self) -> bool {
+        let now = exec::block_timestamp();
+        let mut expired: Vec<(ActorId, ActorId)> = self
+            .get()
+            .allowance_expiry
+            .iter()
+            .filter(|(_, deadline)| now > **deadline)
+            .map(|(key, _)| *key)
+            .collect();
+        let more = expired.len() > MAX_ALLOWANCE_PRUNE;
+        expired.sort_unstable();
+        expired.truncate(MAX_ALLOWANCE_PRUNE);
+
+        let count = expired.len() as u32;
+        for key in expired {
+            Storage::allowances().remove(&key);
+            self.get_mut().allowance_expiry.remove(&key);
+        }
+        if count > 0 {
+            self.emit_event(Event::AllowancesPruned { count })
                 .expect("Notification Error");
         }
-        mutated
+        more
     }
 
-    /// Burn tokens from account; must be allowed by burner.
-    pub fn burn(&mut self, from: ActorId, value: U256) -> bool {
-        if !self.get().burners.contains(&msg::source()) {
-            panic!("Not allowed to burn")
+    /// Transfer tokens of the caller; blocked while paused or for blacklisted accounts.
+    /// The transfer fee and auto-burn, if any, are deducted from `value`. Transfer hooks,
+    /// if registered, are consulted before and notified after.
+    pub async fn transfer(&mut self, to: ActorId, value: U256) -> bool {
+        let from = msg::source();
+        let shares = self.to_shares(value);
+        !self.do_transfer(None, from, to, shares).await.is_zero()
+    }
+
+    /// Transfer on behalf of `from`; an expired allowance is rejected. The transfer fee and
+    /// auto-burn, if any, are deducted from `value`; the allowance is consumed for all of it.
+    /// Transfer hooks apply as for `transfer`.
+    pub async fn transfer_from(&mut self, from: ActorId, to: ActorId, value: U256) -> bool {
+        let spender = msg::source();
+        self.ensure_not_blacklisted(spender);
+        if self.is_allowance_expired(from, spender) {
+            panic!("Allowance expired")
         };
+        let shares = self.to_shares(value);
+        !self.do_transfer(Some(spender), from, to, shares).await.is_zero()
+    }
 
-        let mutated = utils::panicking(|| {
-            burn(Storage::balances(), Storage::total_supply(), from, value)
-        });
+    /// Mint new tokens; must be allowed by minter.
+    pub fn mint(&mut self, to: ActorId, value: U256) -> bool {
+        // Only minters are allowed
+        self.ensure_is_minter();
+        self.ensure_below_mint_threshold(value);
+
+        let shares = self.to_shares(value);
+        let mutated = self.do_mint(to, shares);
         if mutated {
-            self.emit_event(Event::Burned { from, value })
-                .expect("Notification Error");
+            self.record_minter_stats(shares);
+            self.emit_minted(to, value);
         }
         mutated
     }
 
-    /// Grant admin role to another ActorId; requires admin rights.
-    pub fn grant_admin_role(&mut self, to: ActorId) {
-        self.ensure_is_admin();
-        self.get_mut().admins.insert(to);
+    /// Mint like `mint`, at most once per `operation_id`, so a client retrying a timed-out
+    /// message can't mint twice; must be allowed by minter.
+    pub fn mint_with_id(&mut self, to: ActorId, value: U256, operation_id: H256) -> bool {
+        self.ensure_is_minter();
+        if !self.get_mut().processed_mint_ids.insert(operation_id) {
+            panic!("Operation already processed")
+        };
+        self.mint(to, value)
     }
-    /// Grant minter role; requires admin rights.
-    pub fn grant_minter_role(&mut self, to: ActorId) {
+
+    /// Require mints adding up to more than `threshold` per `MINT_THRESHOLD_PERIOD_MS` to be
+    /// proposed and approved by `required` admins, or lift the requirement with `None`;
+    /// requires admin rights. Once enabled, changes to
+    /// the multisig and to the set of admins need the same approvals, through
+    /// `propose_multisig_action`. Bridge mints are held to the threshold like `mint`. Drips,
+    /// faucet mints and airdrop claims are exempt, being bounded by the admin-set schedule,
+    /// faucet caps and airdrop total; so are flash mints, repaid within the same message,
+    /// and `import_state`, which only runs before `finish_import`.
+    pub fn set_mint_multisig(&mut self, config: Option<(U256, u32)>) {
         self.ensure_is_admin();
-        self.get_mut().minters.insert(to);
+        self.ensure_no_multisig();
+        self.execute_multisig_action(MultisigAction::SetMultisig(config));
     }
-    /// Grant burner role; requires admin rights.
-    pub fn grant_burner_role(&mut self, to: ActorId) {
-        self.ensure_is_admin();
-        self.get_mut().burners.insert(to);
+
+    /// Propose minting `value` to `to`, counting as the first approval; requires admin
+    /// rights. Returns the proposal id.
+    pub fn propose_mint(&mut self, to: ActorId, value: U256) -> u64 {
+        self.propose_multisig_action(MultisigAction::Mint { to, value })
     }
 
-    /// Revoke admin role; requires admin rights.
-    pub fn revoke_admin_role(&mut self, from: ActorId) {
+    /// Propose a multisig action, counting as the first approval; requires admin rights.
+    /// Without a multisig it is executed right away. Returns the proposal id.
+    pub fn propose_multisig_action(&mut self, action: MultisigAction) -> u64 {
         self.ensure_is_admin();
-        self.get_mut().admins.remove(&from);
+        let proposer = msg::source();
+        let storage = self.get_mut();
+        let id = storage.next_proposal_id;
+        storage.next_proposal_id += 1;
+        storage.proposals.insert(
+            id,
+            MultisigProposal { proposer, action: action.clone(), approvals: Vec::new() },
+        );
+        self.emit_event(Event::Proposed { id, proposer, action })
+            .expect("Notification Error");
+        self.approve_proposal(id);
+        id
     }
-    /// Revoke minter role; requires admin rights.
-    pub fn revoke_minter_role(&mut self, from: ActorId) {
+
+    /// Approve multisig proposal `id`; requires admin rights. The action is executed once
+    /// enough current admins approved it. Returns whether it was executed and, for a mint,
+    /// whether it minted.
+    pub fn approve_proposal(&mut self, id: u64) -> bool {
         self.ensure_is_admin();
-        self.get_mut().minters.remove(&from);
+        let admin = msg::source();
+        let storage = self.get_mut();
+        let Some(proposal) = storage.proposals.get_mut(&id) else {
+            panic!("Proposal not found")
+        };
+        if proposal.approvals.contains(&admin) {
+            panic!("Already approved")
+        };
+        proposal.approvals.push(admin);
+        // Approvals of revoked admins don't count
+        let approvals = proposal
+            .approvals
+            .iter()
+            .filter(|approver| storage.admins.contains(approver))
+            .count() as u32;
+        self.emit_event(Event::ProposalApproved { id, admin, approvals })
+            .expect("Notification Error");
+
+        let required = self.get().mint_multisig.map_or(1, |(_, required)| required);
+        if approvals < required {
+            return false;
+        }
+        let proposal = self.get_mut().proposals.remove(&id).expect("Proposal exists");
+        self.execute_multisig_action(proposal.action)
     }
-    /// Revoke burner role; requires admin rights.
-    pub fn revoke_burner_role(&mut self, from: ActorId) {
+
+    /// Drop multisig proposal `id`; allowed to its proposer.
+    pub fn cancel_proposal(&mut self, id: u64) {
+        let storage = self.get_mut();
+        match storage.proposals.get(&id) {
+            Some(proposal) if proposal.proposer == msg::source() => {}
+            Some(_) => panic!("Not allowed to cancel"),
+            None => panic!("Proposal not found"),
+        }
+        storage.proposals.remove(&id);
+        self.emit_event(Event::ProposalCancelled { id })
+            .expect("Notification Error");
+    }
+
+    /// Start emitting `initial_rate` tokens per block to `recipient` from now on, halved every
+    /// `halving_interval` blocks, or stop emissions with `None`; requires admin rights.
+    /// Emissions pending under a previous schedule are dropped, so `drip` first.
+    pub fn set_emission_schedule(&mut self, schedule: Option<(ActorId, U256, u32)>) {
         self.ensure_is_admin();
-        self.get_mut().burners.remove(&from);
+        let block = exec::block_height();
+        let schedule = schedule.map(|(recipient, initial_rate, halving_interval)| {
+            if halving_interval == 0 {
+                panic!("Halving interval must be positive")
+            };
+            EmissionSchedule {
+                recipient,
+                initial_rate,
+                halving_interval,
+                start_block: block,
+                dripped_until: block,
+            }
+        });
+        self.get_mut().emission = schedule.clone();
+        self.emit_event(Event::EmissionScheduleSet { schedule })
+            .expect("Notification Error");
     }
 
-    /// List all minters
-    pub fn minters(&self) -> Vec<ActorId> {
-        self.get().minters.clone().into_iter().collect()
+    /// Mint the emissions accrued since the last drip to the emission recipient, capped by
+    /// the max supply; open to anyone. Returns the amount minted.
+    pub fn drip(&mut self) -> U256 {
+        let Some(schedule) = self.get().emission.clone() else {
+            panic!("No emission schedule")
+        };
+        let block = exec::block_height();
+        let mut value = schedule.emitted(schedule.dripped_until, block);
+        if let Some(remaining) = self.mintable_remaining() {
+            value = value.min(remaining);
+        }
+        if let Some(emission) = self.get_mut().emission.as_mut() {
+            emission.dripped_until = block;
+        }
+        if value.is_zero() {
+            return value;
+        }
+
+        self.do_mint(schedule.recipient, self.to_shares(value));
+        self.emit_event(Event::EmissionDripped { to: schedule.recipient, value, up_to_block: block })
+            .expect("Notification Error");
+        value
     }
-    /// List all burners
-    pub fn burners(&self) -> Vec<ActorId> {
-        self.get().burners.clone().into_iter().collect()
+
+    /// Enable the faucet with `config`, or disable it with `None`; requires admin rights.
+    pub fn set_faucet(&mut self, config: Option<FaucetConfig>) {
+        self.ensure_is_admin();
+        self.get_mut().faucet = config.clone();
+        self.emit_event(Event::FaucetSet { config })
+            .expect("Notification Error");
     }
-    /// List all admins
-    pub fn admins(&self) -> Vec<ActorId> {
-        self.get().admins.clone().into_iter().collect()
+
+    /// Mint the faucet amount to the caller while the faucet is enabled, once per cooldown.
+    pub fn faucet(&mut self) -> U256 {
+        let to = msg::source();
+        let Some(config) = self.get().faucet.clone() else {
+            panic!("Faucet is disabled")
+        };
+        if self.faucet_available_at(to).is_some_and(|block| exec::block_height() < block) {
+            panic!("Faucet cooldown")
+        };
+
+        let now = exec::block_timestamp();
+        let storage = self.get_mut();
+        if now.saturating_sub(storage.faucet_day_start) >= FAUCET_DAY_MS {
+            storage.faucet_day_start = now;
+            storage.faucet_minted_today = U256::zero();
+        }
+        let minted_today = storage.faucet_minted_today.saturating_add(config.amount);
+        if minted_today > config.daily_cap {
+            panic!("Faucet daily cap reached")
+        };
+        storage.faucet_minted_today = minted_today;
+        storage.faucet_last_claim.insert(to, exec::block_height());
+
+        self.do_mint(to, self.to_shares(config.amount));
+        self.emit_event(Event::FaucetDripped { to, value: config.amount })
+            .expect("Notification Error");
+        config.amount
     }
-}
 
-impl ExtendedService {
-    fn ensure_is_admin(&self) {
-        if !self.get().admins.contains(&msg::source()) {
-            panic!("Not admin")
+    /// Mint `value` to `to` in `delay_blocks` blocks through a delayed message to this
+    /// program; must be allowed by minter. Returns the schedule id.
+    pub fn schedule_mint(&mut self, to: ActorId, value: U256, delay_blocks: u32) -> u64 {
+        let source = msg::source();
+        self.ensure_is_minter();
+        if delay_blocks == 0 {
+            panic!("Delay must be positive")
         };
+        self.ensure_below_mint_threshold(value);
+
+        let storage = self.get_mut();
+        let id = storage.next_scheduled_mint_id;
+        storage.next_scheduled_mint_id += 1;
+        let execute_at = exec::block_height().saturating_add(delay_blocks);
+        storage.scheduled_mints.insert(id, ScheduledMint { scheduled_by: source, to, value, execute_at });
+
+        let payload = ("Vft", "ExecuteScheduledMint", id).encode();
+        msg::send_bytes_with_gas_delayed(exec::program_id(), payload, SCHEDULED_MINT_GAS, 0, delay_blocks)
+            .expect("Failed to schedule mint");
+        self.emit_event(Event::MintScheduled { id, to, value, execute_at })
+            .expect("Notification Error");
+        id
     }
-}
-impl AsRef<VftService> for ExtendedService {
-    fn as_ref(&self) -> &VftService {
-        &self.vft
+
+    /// Execute scheduled mint `id`; only called by this program's own delayed message.
+    /// Mints that can't go through anymore, including those whose scheduler is no longer a
+    /// minter, are dropped with `ScheduledMintFailed`.
+    pub fn execute_scheduled_mint(&mut self, id: u64) -> bool {
+        if msg::source() != exec::program_id() {
+            panic!("Only the program itself can execute scheduled mints")
+        };
+        // Cancelled in the meantime
+        let Some(schedule) = self.get_mut().scheduled_mints.remove(&id) else {
+            return false;
+        };
+
+        // The minter may have lost its role since scheduling
+        let value = self.to_shares(schedule.value);
+        let blocked = self
+            .minter_blocked(schedule.scheduled_by)
+            .or_else(|| self.mint_blocked(schedule.to, value));
+        if let Some(reason) = blocked {
+            self.emit_event(Event::ScheduledMintFailed { id, reason: reason.into() })
+                .expect("Notification Error");
+            return false;
+        }
+        let mutated = self.do_mint(schedule.to, value);
+        self.emit_event(Event::ScheduledMintExecuted { id })
+            .expect("Notification Error");
+        if mutated {
+            self.emit_minted(schedule.to, schedule.value);
+        }
+        mutated
+    }
+
+    /// Cancel a pending scheduled mint; allowed to whoever scheduled it and to admins.
+    pub fn cancel_scheduled_mint(&mut self, id: u64) {
+        let source = msg::source();
+        let storage = self.get_mut();
+        let Some(schedule) = storage.scheduled_mints.get(&id) else {
+            panic!("Scheduled mint not found")
+        };
+        if schedule.scheduled_by != source && !storage.admins.contains(&source) {
+            panic!("Not allowed to cancel")
+        };
+        storage.scheduled_mints.remove(&id);
+        self.emit_event(Event::ScheduledMintCancelled { id })
+            .expect("Notification Error");
     }
+
+    /// Mint tokens that vest linearly until `vesting_end_ts`, none before `cliff_ts`; must be
+    /// allowed by minter. Locked tokens can't be transferred or burned until `release`d.
+    pub fn mint_locked(&mut self, to: ActorId, value: U256, cliff_ts: u64, vesting_end_ts: u64) -> bool {
+        self.ensure_is_minter();
+        self.ensure_below_mint_threshold(value);
+        let start = exec::block_timestamp();
+        if cliff_ts < start || vesting_end_ts <= cliff_ts {
+            panic!("Invalid vesting schedule")
+        };
+
+        let shares = self.to_shares(value);
+        let mutated = self.do_mint(to, shares);
+        if mutated {
+            self.get_mut().vesting.entry(to).or_default().push(VestingSchedule {
+                total: shares,
+                released: U256::zero(),
+                start,
+                cliff: cliff_ts,
+                end: vesting_end_ts,
+            });
+            self.emit_event(Event::MintedLocked { to, value, cliff: cliff_ts, end: vesting_end_ts })
+                .expect("Notification Error");
+        }
+        mutated
+    }
+
+    /// Unlock the caller's vested tokens and return the amount released.
+    pub fn release(&mut self) -> U256 {
+        let account = msg::source();
+        let now = exec::block_timestamp();
+        let Some(schedules) = self.get_mut().vesting.get_mut(&account) else {
+            return U256::zero();
+        };
+        let mut value = U256::zero();
+        for schedule in schedules.iter_mut() {
+            let vested = schedule.vested(now);
+            value = value.saturating_add(vested - schedule.released);
+            schedule.released = vested;
+        }
+        schedules.retain(|schedule| schedule.released < schedule.total);
+        if schedules.is_empty() {
+            self.get_mut().vesting.remove(&account);
+        }
+        let value = self.to_tokens(value);
+
+        if !value.is_zero() {
+            self.emit_event(Event::Released { account, value })
+                .expect("Notification Error");
+        }
+        value
+    }
+
+    /// Start an airdrop of up to `total` tokens to the leaves of the Merkle tree with `root`.
+    /// Leaves are keccak256(index ++ account ++ amount), all SCALE-encoded, and inner nodes
+    /// keccak256(left ++ right). Replaces any previous airdrop; requires admin rights.
+    pub fn set_airdrop(&mut self, root: [u8; 32], total: U256) {
+        self.ensure_is_admin();
+        let storage = self.get_mut();
+        storage.airdrop_root = Some(root);
+        storage.airdrop_total = total;
+        storage.airdrop_claimed = U256::zero();
+        storage.airdrop_bitmap.clear();
+        self.emit_event(Event::AirdropSet { root, total })
+            .expect("Notification Error");
+    }
+
+    /// Mint the caller's airdrop allocation at leaf `index`, proven by the sibling hashes
+    /// from the leaf up to the root. Each leaf can be claimed once.
+    pub fn claim(&mut self, index: u32, amount: U256, proof: Vec<[u8; 32]>) -> bool {
+        let account = msg::source();
+        let storage = self.get();
+        let Some(root) = storage.airdrop_root else {
+            panic!("No airdrop")
+        };
+        if self.is_claimed(index) {
+            panic!("Already claimed")
+        };
+        let leaf: [u8; 32] = Keccak256::digest((index, account, amount).encode()).into();
+        if merkle_root(leaf, index, &proof) != root {
+            panic!("Invalid proof")
+        };
+        let claimed = storage.airdrop_claimed.saturating_add(amount);
+        if claimed > storage.airdrop_total {
+            panic!("Airdrop exhausted")
+        };
+
+        let storage = self.get_mut();
+        storage.airdrop_claimed = claimed;
+        *storage.airdrop_bitmap.entry(index / 64).or_default() |= 1 << (index % 64);
+        let mutated = self.do_mint(account, self.to_shares(amount));
+        self.emit_event(Event::AirdropClaimed { account, index, amount })
+            .expect("Notification Error");
+        mutated
+    }
+
+    /// Wrap the attached native value: mint the same amount of tokens to the caller and
+    /// record it as the caller's wrapped backing.
+    pub fn deposit(&mut self) -> bool {
+        let account = msg::source();
+        let value = U256::from(msg::value());
+
+        let mutated = self.do_mint(account, self.to_shares(value));
+        if mutated {
+            let wrapped = self.get_mut().wrapped.entry(account).or_default();
+            *wrapped = wrapped.saturating_add(value);
+            self.emit_event(Event::Deposited { account, value })
+                .expect("Notification Error");
+        }
+        mutated
+    }
+
+    /// Unwrap tokens: burn `value` of the caller's tokens and send the same native value back.
+    /// Only the caller's own wrapped backing can be withdrawn, so tokens minted any other way
+    /// never drain native value deposited by others.
+    pub fn withdraw(&mut self, value: U256) -> bool {
+        let account = msg::source();
+        if value > U256::from(u128::MAX) {
+            panic!("Value is too large")
+        };
+        let wrapped = self.wrapped_of(account);
+        if value > wrapped {
+            panic!("Exceeds wrapped balance")
+        };
+
+        let mutated = self.do_burn(account, self.to_shares(value));
+        if mutated {
+            let storage = self.get_mut();
+            if wrapped == value {
+                storage.wrapped.remove(&account);
+            } else {
+                storage.wrapped.insert(account, wrapped - value);
+            }
+            msg::send_bytes(account, [], value.low_u128()).expect("Error sending value");
+            self.emit_event(Event::Withdrawn { account, value })
+                .expect("Notification Error");
+        }
+        mutated
+    }
+
+    /// Mint to many recipients at once; must be allowed by minter. Any failing credit
+    /// panics, which reverts the whole message, so the batch is all-or-nothing.
+    pub fn mint_batch(&mut self, recipients: Vec<(ActorId, U256)>) -> bool {
+        self.ensure_is_minter();
+        if recipients.is_empty() {
+            panic!("Empty batch")
+        };
+        // A large mint can't be split over a batch
+        let batch_total = recipients
+            .iter()
+            .fold(U256::zero(), |total, (_, value)| total.saturating_add(*value));
+        self.ensure_below_mint_threshold(batch_total);
+
+        let mut count = 0u32;
+        let mut total = U256::zero();
+        for (to, value) in recipients {
+            let shares = self.to_shares(value);
+            if self.do_mint(to, shares) {
+                self.record_minter_stats(shares);
+                count += 1;
+                total = total.saturating_add(value);
+            }
+        }
+        if count > 0 {
+            self.emit_event(Event::MintedBatch { recipients: count, total })
+                .expect("Notification Error");
+        }
+        count > 0
+    }
+
+    /// Mint new tokens and return the recipient's resulting balance; must be allowed by minter.
+    pub fn mint_returning_balance(&mut self, to: ActorId, value: U256) -> U256 {
+        self.mint(to, value);
+        self.balance_of(to)
+    }
+
+    /// Burn tokens from account; must be allowed by burner.
+    pub fn burn(&mut self, from: ActorId, value: U256) -> bool {
+        if !self.get().burners.contains(&msg::source()) {
+            panic!("Not allowed to burn")
+        };
+
+        let mutated = self.do_burn(from, self.to_shares(value));
+        if mutated {
+            self.emit_burned(from, value);
+        }
+        mutated
+    }
+
+    /// Burn from many accounts at once; must be allowed by burner. Any failing debit
+    /// panics, which reverts the whole message, so the batch is all-or-nothing.
+    pub fn burn_batch(&mut self, from: Vec<(ActorId, U256)>) -> bool {
+        if !self.get().burners.contains(&msg::source()) {
+            panic!("Not allowed to burn")
+        };
+
+        let mut count = 0u32;
+        let mut total = U256::zero();
+        for (account, value) in from {
+            if self.do_burn(account, self.to_shares(value)) {
+                count += 1;
+                total = total.saturating_add(value);
+            }
+        }
+        if count > 0 {
+            self.emit_event(Event::BurnedBatch { accounts: count, total })
+                .expect("Notification Error");
+        }
+        count > 0
+    }
+
+    /// Burn tokens of `owner` using the caller's allowance instead of the burner role.
+    pub fn burn_from(&mut self, owner: ActorId, value: U256) -> bool {
+        let spender = msg::source();
+        if self.is_allowance_expired(owner, spender) {
+            panic!("Allowance expired")
+        };
+        let shares = self.to_shares(value);
+        spend_allowance(owner, spender, shares);
+
+        let mutated = self.do_burn(owner, shares);
+        if mutated {
+            self.emit_burned(owner, value);
+        }
+        mutated
+    }
+
+    /// Burn tokens recording a bookkeeping reason (e.g. "redemption") in the burn log;
+    /// must be allowed by burner.
+    pub fn burn_with_reason(&mut self, from: ActorId, value: U256, reason: String) -> bool {
+        if !self.get().burners.contains(&msg::source()) {
+            panic!("Not allowed to burn")
+        };
+        if reason.len() > MAX_REASON_LENGTH {
+            panic!("Reason is too long")
+        };
+
+        let mutated = self.do_burn(from, self.to_shares(value));
+        if mutated {
+            self.record_burn(BurnRecord {
+                block: exec::block_height(),
+                burner: msg::source(),
+                from,
+                value,
+                reason: reason.clone(),
+            });
+            self.emit_event(Event::BurnedWithReason { from, value, reason })
+                .expect("Notification Error");
+        }
+        mutated
+    }
+
+    /// Pull tokens from many accounts that approved the caller into `to`. Each source gives
+    /// up to min(allowance, balance), with the usual checks, fees and hooks; sources without
+    /// approval or balance are skipped rather than reverting the whole sweep. Emits a Transfer
+    /// per source and a Swept summary.
+    pub async fn sweep(&mut self, from: Vec<ActorId>, to: ActorId) -> U256 {
+        self.ensure_not_paused();
+        let spender = msg::source();
+        self.ensure_not_blacklisted(spender);
+
+        let mut transfers: Vec<(ActorId, ActorId, U256)> = Vec::new();
+        for source in from {
+            if transfers.iter().any(|(seen, _, _)| *seen == source) {
+                continue;
+            }
+            if self.transfer_blocked(source, to).is_some() {
+                continue;
+            }
+            let allowance = self.allowance_shares(source, spender);
+            let balance = funcs::balance_of(Storage::balances(), source);
+            let value = allowance.min(balance);
+            if value.is_zero() {
+                continue;
+            }
+            if self.remaining_spend(source).is_some_and(|remaining| self.to_tokens(value) > remaining) {
+                continue;
+            }
+            transfers.push((source, to, value));
+        }
+
+        let received = self.do_transfers(Some(spender), &transfers).await;
+        let mut sources = 0u32;
+        let mut total = U256::zero();
+        for ((_, _, shares), received) in transfers.iter().zip(received) {
+            if !received.is_zero() {
+                sources += 1;
+                total = total.saturating_add(self.to_tokens(*shares));
+            }
+        }
+
+        if sources > 0 {
+            self.emit_event(Event::Swept { to, sources, total })
+                .expect("Notification Error");
+        }
+        total
+    }
+
+    /// Move balances below `threshold` to the collector `to`, at most `MAX_DUST_SWEEP`
+    /// accounts per call. Only the unlocked, unfrozen part is swept; this program's escrow
+    /// and registered token-bound accounts are left alone. Returns whether dust accounts
+    /// remain; requires admin rights.
+    pub fn sweep_dust(&mut self, threshold: U256, to: ActorId) -> bool {
+        self.ensure_is_admin();
+        self.ensure_not_paused();
+        let program = exec::program_id();
+        let storage = self.get();
+        let mut dust: Vec<(ActorId, U256)> = storage
+            .balance_index
+            .range(..(self.to_shares(threshold), ActorId::zero()))
+            .filter(|(_, account)| {
+                *account != to
+                    && *account != program
+                    && !storage.token_accounts.contains(account)
+                    && self.transfer_blocked(*account, to).is_none()
+            })
+            .map(|(balance, account)| {
+                let free = balance
+                    .saturating_sub(self.locked_shares(*account))
+                    .saturating_sub(self.frozen_shares(*account));
+                (*account, free)
+            })
+            .filter(|(_, free)| !free.is_zero())
+            .collect();
+        let more = dust.len() > MAX_DUST_SWEEP;
+        dust.truncate(MAX_DUST_SWEEP);
+
+        let mut accounts = 0u32;
+        let mut total = U256::zero();
+        for (account, balance) in dust {
+            let prior = self.before_balance_change(&[account, to]);
+            if utils::panicking(|| funcs::transfer(Storage::balances(), account, to, balance)) {
+                accounts += 1;
+                total = total.saturating_add(self.to_tokens(balance));
+            }
+            self.after_balance_change(prior);
+        }
+
+        if accounts > 0 {
+            self.emit_event(Event::DustSwept { to, accounts, total })
+                .expect("Notification Error");
+        }
+        more
+    }
+
+    /// Register the account bound to `token_id` of the calling VNFT program, so it is never
+    /// swept as dust.
+    pub fn register_token_account(&mut self, token_id: U256) {
+        let account = token_account(msg::source(), token_id);
+        self.get_mut().token_accounts.insert(account);
+    }
+
+    /// Move the whole balance bound to `token_id` of the calling VNFT program to `to`, with
+    /// the usual checks, fees and hooks. Token-bound accounts are derived from (VNFT program,
+    /// token id) with `token_account`, so a program can only ever move balances of accounts
+    /// derived from its own id.
+    pub async fn token_bound_transfer(&mut self, token_id: U256, to: ActorId) -> U256 {
+        let nft_program = msg::source();
+        let from = token_account(nft_program, token_id);
+        self.get_mut().token_accounts.insert(from);
+        let shares = self.shares_of(from);
+        let value = self.to_tokens(shares);
+
+        let mutated = !self.do_transfer(None, from, to, shares).await.is_zero();
+        if mutated {
+            self.emit_event(Event::TokenBoundTransfer { nft_program, token_id, to, value })
+                .expect("Notification Error");
+        }
+        value
+    }
+
+    /// Approve `spender` on behalf of `owner` using the owner's sr25519 signature over a
+    /// `PermitMessage`, so the owner doesn't have to send the transaction. Each permit
+    /// consumes the owner's current nonce, which makes every signature single-use.
+    pub fn permit(
+        &mut self,
+        owner: ActorId,
+        spender: ActorId,
+        value: U256,
+        deadline: u64,
+        signature: Vec<u8>,
+    ) -> bool {
+        if exec::block_timestamp() > deadline {
+            panic!("Permit expired")
+        };
+        let nonce = self.permit_nonce(owner);
+        let message = PermitMessage {
+            program: exec::program_id(),
+            owner,
+            spender,
+            value,
+            nonce,
+            deadline,
+        };
+        if !verify_signature(owner, &message.encode(), &signature) {
+            panic!("Invalid signature")
+        };
+
+        self.get_mut().permit_nonces.insert(owner, nonce + 1);
+        self.get_mut().allowance_expiry.remove(&(owner, spender));
+        let mutated = funcs::approve(Storage::allowances(), owner, spender, self.to_shares(value));
+        self.emit_event(Event::Permit { owner, spender, value, nonce })
+            .expect("Notification Error");
+        mutated
+    }
+
+    /// Mint `amount` to the calling program, send it `callback_payload` and, once it replies,
+    /// take back `amount` plus the flash fee from its balance; must be allowed by flash
+    /// borrower. The mint can't be undone once the callback was sent, so on a failed callback
+    /// or short repayment as much of `amount` as the borrower can spend is burned, the
+    /// borrower loses the role and false is returned.
+    pub async fn flash_mint(&mut self, amount: U256, callback_payload: Vec<u8>) -> bool {
+        let borrower = msg::source();
+        if !self.get().flash_borrowers.contains(&borrower) {
+            panic!("Not allowed to flash mint")
+        };
+        let fee = amount.saturating_mul(U256::from(self.get().flash_fee_bps)) / U256::from(10_000u64);
+        let shares = self.to_shares(amount);
+        self.do_mint(borrower, shares);
+
+        let replied = match msg::send_bytes_for_reply(borrower, callback_payload, 0, 0) {
+            Ok(future) => future.await.is_ok(),
+            Err(_) => false,
+        };
+
+        let fee_shares = self.to_shares(fee);
+        let available = funcs::balance_of(Storage::balances(), borrower)
+            .saturating_sub(self.locked_shares(borrower))
+            .saturating_sub(self.frozen_shares(borrower));
+        if !replied || available < shares.saturating_add(fee_shares) {
+            let recovered = available.min(shares);
+            self.burn_balance(borrower, recovered);
+            if self.get_mut().flash_borrowers.remove(&borrower) {
+                self.emit_role_change(Role::FlashBorrower, borrower, false);
+            }
+            self.emit_event(Event::FlashMintDefaulted {
+                borrower,
+                amount,
+                recovered: self.to_tokens(recovered),
+            })
+            .expect("Notification Error");
+            return false;
+        }
+
+        self.burn_balance(borrower, shares);
+        match self.get().fee_collector {
+            Some(collector) if !fee_shares.is_zero() => {
+                self.move_balance(borrower, collector, fee_shares);
+            }
+            _ => {
+                self.burn_balance(borrower, fee_shares);
+            }
+        }
+        self.emit_event(Event::FlashMinted { borrower, amount, fee })
+            .expect("Notification Error");
+        true
+    }
+
+    /// Charge `bps` basis points on flash mints, paid to the fee collector or burned if there
+    /// is none. Requires admin rights.
+    pub fn set_flash_fee(&mut self, bps: u16) {
+        self.ensure_is_admin();
+        if bps > 10_000 {
+            panic!("Flash fee cannot exceed 10000 bps")
+        };
+        self.get_mut().flash_fee_bps = bps;
+        self.emit_event(Event::FlashFeeSet { bps })
+            .expect("Notification Error");
+    }
+
+
+    /// Transfer like `transfer`, attaching a memo (e.g. a deposit reference) to the event.
+    pub async fn transfer_with_memo(&mut self, to: ActorId, value: U256, memo: String) -> bool {
+        if memo.len() > MAX_MEMO_LENGTH {
+            panic!("Memo is too long")
+        };
+        let from = msg::source();
+        let shares = self.to_shares(value);
+        let mutated = !self.do_transfer(None, from, to, shares).await.is_zero();
+        if mutated {
+            self.emit_event(Event::TransferredWithMemo { from, to, value, memo })
+                .expect("Notification Error");
+        }
+        mutated
+    }
+
+    /// Send tokens of the caller to many recipients at once, each transfer with the usual
+    /// checks, fees and hooks. Any failing transfer panics, which reverts the whole batch.
+    pub async fn transfer_batch(&mut self, recipients: Vec<(ActorId, U256)>) -> bool {
+        if recipients.is_empty() {
+            panic!("Empty batch")
+        };
+        let from = msg::source();
+        let total = recipients
+            .iter()
+            .try_fold(U256::zero(), |acc, (_, value)| acc.checked_add(*value))
+            .expect("Total overflows");
+        if self.balance_of(from) < total {
+            panic!("Insufficient balance")
+        };
+
+        let count = recipients.len() as u32;
+        let transfers: Vec<(ActorId, ActorId, U256)> = recipients
+            .into_iter()
+            .map(|(to, value)| (from, to, self.to_shares(value)))
+            .collect();
+        self.do_transfers(None, &transfers).await;
+        self.emit_event(Event::TransferredBatch { from, recipients: count, total })
+            .expect("Notification Error");
+        true
+    }
+
+    /// Transfer to the program `to` and then send it `payload`, so it can react to the
+    /// incoming tokens; nothing is sent if nothing was received. If the call fails, as much
+    /// of the amount `to` received as it can still spend is moved back and reported with
+    /// `TransferCallRefunded`; the transfer fee and auto-burn are not refunded.
+    pub async fn transfer_and_call(&mut self, to: ActorId, value: U256, payload: Vec<u8>) -> bool {
+        let from = msg::source();
+        let shares = self.to_shares(value);
+        let received = self.do_transfer(None, from, to, shares).await;
+        if received.is_zero() {
+            return false;
+        }
+
+        let reply = match msg::send_bytes_for_reply(to, payload, 0, 0) {
+            Ok(future) => future.await.is_ok(),
+            Err(_) => false,
+        };
+        if !reply {
+            // `to` may have spent, frozen or locked the tokens meanwhile
+            let available = self
+                .shares_of(to)
+                .saturating_sub(self.locked_shares(to))
+                .saturating_sub(self.frozen_shares(to));
+            let refunded = available.min(received);
+            if !refunded.is_zero() {
+                self.move_balance(to, from, refunded);
+            }
+            self.emit_event(Event::TransferCallRefunded {
+                from,
+                to,
+                received: self.to_tokens(received),
+                refunded: self.to_tokens(refunded),
+            })
+            .expect("Notification Error");
+            return false;
+        }
+
+        self.emit_event(Event::TransferredAndCalled { from, to, value })
+            .expect("Notification Error");
+        true
+    }
+
+    /// Transfer from `from` as signed off-chain by `from` over a `TransferMessage`, so a
+    /// relayer can pay for the transaction. Uses and consumes the same nonce as `permit`.
+    /// The nonce is consumed once the signature checks out, even if the transfer itself is
+    /// then rejected (e.g. by a transfer hook), so a signed message can't be replayed later;
+    /// `TransferBySig` is only emitted when tokens moved.
+    pub async fn transfer_by_sig(
+        &mut self,
+        from: ActorId,
+        to: ActorId,
+        value: U256,
+        nonce: u64,
+        deadline: u64,
+        signature: Vec<u8>,
+    ) -> bool {
+        if exec::block_timestamp() > deadline {
+            panic!("Signature expired")
+        };
+        if nonce != self.permit_nonce(from) {
+            panic!("Invalid nonce")
+        };
+        let message = TransferMessage {
+            program: exec::program_id(),
+            from,
+            to,
+            value,
+            nonce,
+            deadline,
+        };
+        if !verify_signature(from, &message.encode(), &signature) {
+            panic!("Invalid signature")
+        };
+        self.get_mut().permit_nonces.insert(from, nonce + 1);
+
+        let shares = self.to_shares(value);
+        let mutated = !self.do_transfer(None, from, to, shares).await.is_zero();
+        if mutated {
+            self.emit_event(Event::TransferBySig { from, to, value, nonce, relayer: msg::source() })
+                .expect("Notification Error");
+        }
+        mutated
+    }
+
+    /// Auto-pause when burns within `window_ms` exceed `bps` of the supply; `None` disables
+    /// it. The tripping burn itself still goes through. Requires admin rights.
+    pub fn set_burn_circuit_breaker(&mut self, config: Option<(u16, u64)>) {
+        self.ensure_is_admin();
+        if let Some((bps, window_ms)) = config {
+            if bps > 10_000 || window_ms == 0 {
+                panic!("Invalid circuit breaker")
+            };
+        }
+        let storage = self.get_mut();
+        storage.burn_circuit_breaker = config;
+        storage.burn_window_start = exec::block_timestamp();
+        storage.burned_in_window = U256::zero();
+    }
+
+    /// Scale every balance by `delta_ppm` parts per million (negative to shrink) by moving
+    /// the share index; balances are stored as shares, so no account is touched. Requires
+    /// admin rights.
+    pub fn rebase(&mut self, delta_ppm: i32) -> U256 {
+        self.ensure_is_admin();
+        self.accrue();
+        let factor = 1_000_000i64 + delta_ppm as i64;
+        if factor <= 0 {
+            panic!("Rebase would wipe out balances")
+        };
+        let index = self.index().saturating_mul(U256::from(factor as u64)) / U256::from(1_000_000u64);
+        if index.is_zero() {
+            panic!("Rebase would wipe out balances")
+        };
+        self.get_mut().rebase_index = index;
+        self.emit_event(Event::Rebased { delta_ppm, index })
+            .expect("Notification Error");
+        index
+    }
+
+    /// Grow every balance by `ppm_per_block` parts per million each block through the
+    /// rebase index, accrued lazily and capped by the max supply; 0 stops accrual. Requires
+    /// admin rights.
+    pub fn set_interest_rate(&mut self, ppm_per_block: u32) {
+        self.ensure_is_admin();
+        // Interest so far accrues at the old rate
+        self.accrue();
+        self.get_mut().interest_rate_ppm = ppm_per_block;
+        self.emit_event(Event::InterestRateSet { ppm_per_block })
+            .expect("Notification Error");
+    }
+
+    /// Record the current balances and total supply under a new snapshot id, returned for
+    /// `balance_of_at` and `total_supply_at`. Requires admin rights.
+    pub fn snapshot(&mut self) -> u64 {
+        self.ensure_is_admin();
+        self.take_snapshot()
+    }
+
+    /// Send tokens to `to` that the program holds until `release_timestamp`; returns the lock id.
+    /// Moving the tokens to the program goes through the usual checks and hooks.
+    pub async fn transfer_locked(&mut self, to: ActorId, value: U256, release_timestamp: u64) -> u64 {
+        let from = msg::source();
+        self.ensure_can_transfer(from, to);
+        if value.is_zero() {
+            panic!("Nothing to lock")
+        };
+        let shares = self.to_shares(value);
+        self.do_transfer(None, from, exec::program_id(), shares).await;
+
+        let storage = self.get_mut();
+        let id = storage.next_lock_id;
+        storage.next_lock_id += 1;
+        storage.locks.insert(id, TimeLock { from, to, value: shares, release_at: release_timestamp });
+        self.emit_event(Event::TransferLocked { id, from, to, value, release_at: release_timestamp })
+            .expect("Notification Error");
+        id
+    }
+
+    /// Receive the tokens of lock `id` once released, through the usual checks and hooks;
+    /// only its recipient can claim.
+    pub async fn claim_locked(&mut self, id: u64) -> U256 {
+        self.ensure_not_paused();
+        let Some(lock) = self.get().locks.get(&id).cloned() else {
+            panic!("No such lock")
+        };
+        if lock.to != msg::source() {
+            panic!("Not lock recipient")
+        };
+        if exec::block_timestamp() < lock.release_at {
+            panic!("Not released yet")
+        };
+        self.do_transfer(None, exec::program_id(), lock.to, lock.value).await;
+        // Claimed or cancelled while awaiting the transfer hook
+        if self.get_mut().locks.remove(&id).is_none() {
+            panic!("No such lock")
+        };
+        self.emit_event(Event::LockClaimed { id })
+            .expect("Notification Error");
+        self.to_tokens(lock.value)
+    }
+
+    /// Take back the tokens of lock `id` before its release time, if locks are cancelable,
+    /// through the usual checks and hooks; only its sender can cancel.
+    pub async fn cancel_before_release(&mut self, id: u64) -> U256 {
+        self.ensure_not_paused();
+        if !self.get().locks_cancelable {
+            panic!("Locks are not cancelable")
+        };
+        let Some(lock) = self.get().locks.get(&id).cloned() else {
+            panic!("No such lock")
+        };
+        if lock.from != msg::source() {
+            panic!("Not lock sender")
+        };
+        if exec::block_timestamp() >= lock.release_at {
+            panic!("Already released")
+        };
+        self.do_transfer(None, exec::program_id(), lock.from, lock.value).await;
+        // Claimed or cancelled while awaiting the transfer hook
+        if self.get_mut().locks.remove(&id).is_none() {
+            panic!("No such lock")
+        };
+        self.emit_event(Event::LockCancelled { id })
+            .expect("Notification Error");
+        self.to_tokens(lock.value)
+    }
+
+    /// Allow or forbid senders to cancel time locks before release; requires admin rights.
+    pub fn set_locks_cancelable(&mut self, cancelable: bool) {
+        self.ensure_is_admin();
+        self.get_mut().locks_cancelable = cancelable;
+        self.emit_event(Event::LocksCancelableSet { cancelable })
+            .expect("Notification Error");
+    }
+
+    /// Stream tokens of the caller to `to` at `rate_per_block` until `end_block`. The whole
+    /// amount is moved to the program up front, through the usual checks and hooks; returns
+    /// the stream id.
+    pub async fn create_stream(&mut self, to: ActorId, rate_per_block: U256, end_block: u32) -> u64 {
+        let sender = msg::source();
+        self.ensure_can_transfer(sender, to);
+        let stream = Stream {
+            sender,
+            recipient: to,
+            rate_per_block: self.to_shares(rate_per_block),
+            start_block: exec::block_height(),
+            end_block,
+            withdrawn: U256::zero(),
+        };
+        if stream.deposit().is_zero() {
+            panic!("Empty stream")
+        };
+        self.do_transfer(None, sender, exec::program_id(), stream.deposit()).await;
+
+        let storage = self.get_mut();
+        let id = storage.next_stream_id;
+        storage.next_stream_id += 1;
+        storage.streams.insert(id, stream);
+        self.emit_event(Event::StreamCreated { id, sender, recipient: to, rate_per_block, end_block })
+            .expect("Notification Error");
+        id
+    }
+
+    /// Pay the recipient of stream `id` what has streamed so far, through the usual checks
+    /// and hooks; only the recipient can.
+    pub async fn withdraw_from_stream(&mut self, stream_id: u64) -> U256 {
+        self.ensure_not_paused();
+        let Some(stream) = self.get().streams.get(&stream_id).cloned() else {
+            panic!("No such stream")
+        };
+        if stream.recipient != msg::source() {
+            panic!("Not stream recipient")
+        };
+        let shares = stream.streamed(exec::block_height()) - stream.withdrawn;
+        self.do_transfer(None, exec::program_id(), stream.recipient, shares).await;
+
+        let Some(current) = self.get_mut().streams.get_mut(&stream_id) else {
+            panic!("No such stream")
+        };
+        if current.withdrawn != stream.withdrawn {
+            panic!("Stream changed while withdrawing")
+        };
+        current.withdrawn = current.withdrawn.saturating_add(shares);
+        if current.withdrawn == current.deposit() {
+            self.get_mut().streams.remove(&stream_id);
+        }
+        let value = self.to_tokens(shares);
+        self.emit_event(Event::StreamWithdrawn { id: stream_id, value })
+            .expect("Notification Error");
+        value
+    }
+
+    /// Stop stream `id`: the recipient gets what has streamed so far and the sender the
+    /// rest, both through the usual checks and hooks. Only the sender can cancel.
+    pub async fn cancel_stream(&mut self, stream_id: u64) {
+        self.ensure_not_paused();
+        let Some(stream) = self.get().streams.get(&stream_id).cloned() else {
+            panic!("No such stream")
+        };
+        if stream.sender != msg::source() {
+            panic!("Not stream sender")
+        };
+        let streamed = stream.streamed(exec::block_height());
+        let paid = streamed - stream.withdrawn;
+        let refunded = stream.deposit() - streamed;
+
+        let program = exec::program_id();
+        let payouts: Vec<(ActorId, ActorId, U256)> = [
+            (program, stream.recipient, paid),
+            (program, stream.sender, refunded),
+        ]
+        .into_iter()
+        .filter(|(_, _, value)| !value.is_zero())
+        .collect();
+        self.do_transfers(None, &payouts).await;
+        match self.get_mut().streams.remove(&stream_id) {
+            Some(current) if current.withdrawn == stream.withdrawn => {}
+            _ => panic!("Stream changed while cancelling"),
+        }
+        let (paid, refunded) = (self.to_tokens(paid), self.to_tokens(refunded));
+        self.emit_event(Event::StreamCancelled { id: stream_id, paid, refunded })
+            .expect("Notification Error");
+    }
+
+    /// Share the attached native value among holders pro rata to their balances at a new
+    /// snapshot; they claim it with `claim_dividend`. Requires admin rights. Tokens held in
+    /// the program's own escrow get no share, so the whole value goes to holders.
+    pub fn deposit_dividend(&mut self) -> u32 {
+        self.ensure_is_admin();
+        let amount = msg::value();
+        if amount == 0 {
+            panic!("No value attached")
+        };
+        let snapshot_id = self.take_snapshot();
+        if self.dividend_supply(snapshot_id).is_zero() {
+            panic!("No holders")
+        };
+        let dividends = &mut self.get_mut().dividends;
+        let id = dividends.len() as u32;
+        dividends.push(Dividend { snapshot_id, amount, claimed: 0 });
+        self.emit_event(Event::DividendDeposited { id, snapshot_id, amount })
+            .expect("Notification Error");
+        id
+    }
+
+    /// Send the caller its share of every dividend it hasn't claimed yet.
+    pub fn claim_dividend(&mut self) -> u128 {
+        let account = msg::source();
+        let mut amount = 0u128;
+        for id in 0..self.get().dividends.len() as u32 {
+            let share = self.dividend_share(id, account);
+            if share == 0 {
+                continue;
+            }
+            let storage = self.get_mut();
+            storage.dividends_claimed.insert((id, account));
+            storage.dividends[id as usize].claimed += share;
+            amount += share;
+        }
+        if amount == 0 {
+            panic!("Nothing to claim")
+        };
+
+        msg::send_bytes(account, [], amount).expect("Error sending value");
+        self.emit_event(Event::DividendClaimed { account, amount })
+            .expect("Notification Error");
+        amount
+    }
+
+    /// Delegate the caller's voting power (its whole balance, now and as it changes) to `to`.
+    /// Balances count as votes only once delegated; delegate to yourself to vote directly.
+    pub fn delegate(&mut self, to: ActorId) {
+        let delegator = msg::source();
+        let from_delegate = self.get_mut().delegates.insert(delegator, to);
+        let balance = funcs::balance_of(Storage::balances(), delegator);
+        if let Some(previous) = from_delegate {
+            self.move_votes(previous, balance, false);
+        }
+        self.move_votes(to, balance, true);
+        self.emit_event(Event::DelegateChanged { delegator, from_delegate, to_delegate: to })
+            .expect("Notification Error");
+    }
+
+    /// Rename the token; requires admin rights.
+    pub fn set_name(&mut self, name: String) {
+        self.ensure_is_admin();
+        if name.is_empty() || name.len() > MAX_METADATA_LENGTH {
+            panic!("Invalid name")
+        };
+        self.get_mut().name = Some(name);
+        self.emit_metadata_updated();
+    }
+
+    /// Change the token symbol; requires admin rights.
+    pub fn set_symbol(&mut self, symbol: String) {
+        self.ensure_is_admin();
+        if symbol.is_empty() || symbol.len() > MAX_METADATA_LENGTH {
+            panic!("Invalid symbol")
+        };
+        self.get_mut().symbol = Some(symbol);
+        self.emit_metadata_updated();
+    }
+
+    /// Set or clear (`None`) the token icon URI; requires admin rights.
+    pub fn set_icon_uri(&mut self, icon_uri: Option<String>) {
+        self.ensure_is_admin();
+        if icon_uri.as_ref().is_some_and(|uri| uri.len() > MAX_METADATA_LENGTH) {
+            panic!("Icon URI is too long")
+        };
+        self.get_mut().icon_uri = icon_uri;
+        self.emit_metadata_updated();
+        self.emit_extended_metadata_updated();
+    }
+
+    /// Set or clear (`None`) the project URL; requires admin rights.
+    pub fn set_project_url(&mut self, project_url: Option<String>) {
+        self.ensure_is_admin();
+        if project_url.as_ref().is_some_and(|url| url.len() > MAX_METADATA_LENGTH) {
+            panic!("Project URL is too long")
+        };
+        self.get_mut().project_url = project_url;
+        self.emit_extended_metadata_updated();
+    }
+
+    /// Set or clear (`None`) the token description; requires admin rights.
+    pub fn set_description(&mut self, description: Option<String>) {
+        self.ensure_is_admin();
+        if description.as_ref().is_some_and(|text| text.len() > MAX_DESCRIPTION_LENGTH) {
+            panic!("Description is too long")
+        };
+        self.get_mut().description = description;
+        self.emit_extended_metadata_updated();
+    }
+
+    /// Block transfers, mints and burns until `unpause`; allowed to admins and guardians.
+    pub fn pause(&mut self) {
+        let source = msg::source();
+        let storage = self.get();
+        if !storage.admins.contains(&source) && !storage.guardians.contains(&source) {
+            panic!("Not admin or guardian")
+        };
+        self.get_mut().paused = true;
+        self.emit_event(Event::Paused)
+            .expect("Notification Error");
+    }
+
+    /// Resume transfers, mints and burns; requires admin rights.
+    pub fn unpause(&mut self) {
+        self.ensure_is_admin();
+        let storage = self.get_mut();
+        storage.paused = false;
+        storage.burn_window_start = exec::block_timestamp();
+        storage.burned_in_window = U256::zero();
+        self.emit_event(Event::Unpaused)
+            .expect("Notification Error");
+    }
+
+    /// Block an account from sending, receiving, being minted to and being granted roles;
+    /// requires admin rights. Recorded in the compliance log with `reason`.
+    pub fn blacklist(&mut self, account: ActorId, reason: String) {
+        self.ensure_is_admin();
+        if self.get_mut().blacklist.insert(account) {
+            let by = self.record_compliance(ComplianceAction::Blacklist, account, &reason);
+            self.emit_event(Event::Blacklisted { account, by, reason })
+                .expect("Notification Error");
+        }
+    }
+
+    /// Register the transfer hook programs; requires admin rights.
+    pub fn set_transfer_hooks(&mut self, hooks: TransferHooks) {
+        self.ensure_is_admin();
+        self.get_mut().transfer_hooks = hooks.clone();
+        self.emit_event(Event::TransferHooksSet { hooks })
+            .expect("Notification Error");
+    }
+
+    /// Cap what `account` can send per `period_blocks` blocks; `None` removes the cap.
+    /// Requires admin rights.
+    pub fn set_spending_limit(&mut self, account: ActorId, limit: Option<(U256, u32)>) {
+        self.ensure_is_admin();
+        let limits = &mut self.get_mut().spending_limits;
+        match limit {
+            Some((_, 0)) => panic!("Period cannot be empty"),
+            Some((limit, period_blocks)) => {
+                limits.insert(account, SpendingLimit {
+                    limit,
+                    period_blocks,
+                    period_start: exec::block_height(),
+                    spent: U256::zero(),
+                });
+            }
+            None => {
+                limits.remove(&account);
+            }
+        }
+        self.emit_event(Event::SpendingLimitSet { account, limit })
+            .expect("Notification Error");
+    }
+
+    /// Freeze `amount` more of an account's balance, which then can't be transferred or
+    /// burned; requires admin rights. Recorded in the compliance log with `reason`.
+    pub fn freeze(&mut self, account: ActorId, amount: U256, reason: String) {
+        self.ensure_is_admin();
+        let shares = self.to_shares(amount);
+        let frozen = self.get_mut().frozen.entry(account).or_default();
+        *frozen = frozen.saturating_add(shares);
+        let by = self.record_compliance(ComplianceAction::Freeze(amount), account, &reason);
+        self.emit_event(Event::Frozen { account, amount, by, reason })
+            .expect("Notification Error");
+    }
+
+    /// Unfreeze up to `amount` of an account's frozen balance; requires admin rights.
+    /// Recorded in the compliance log with `reason`.
+    pub fn unfreeze(&mut self, account: ActorId, amount: U256, reason: String) {
+        self.ensure_is_admin();
+        let frozen = self.frozen_shares(account);
+        let shares = self.to_shares(amount).min(frozen);
+        if shares == frozen {
+            self.get_mut().frozen.remove(&account);
+        } else {
+            self.get_mut().frozen.insert(account, frozen - shares);
+        }
+        let amount = amount.min(self.to_tokens(frozen));
+        let by = self.record_compliance(ComplianceAction::Unfreeze(amount), account, &reason);
+        self.emit_event(Event::Unfrozen { account, amount, by, reason })
+            .expect("Notification Error");
+    }
+
+    /// Burn the caller's tokens to be minted on `dest_chain` for `dest_address`. Each burn
+    /// gets the next outbound nonce, which the bridge relays along with it.
+    pub fn bridge_burn(&mut self, value: U256, dest_chain: String, dest_address: Vec<u8>) -> u64 {
+        let from = msg::source();
+        if !self.do_burn(from, self.to_shares(value)) {
+            panic!("Nothing to burn")
+        };
+        let storage = self.get_mut();
+        let nonce = storage.bridge_out_nonce;
+        storage.bridge_out_nonce += 1;
+        self.emit_event(Event::BridgeBurned { nonce, from, value, dest_chain, dest_address })
+            .expect("Notification Error");
+        nonce
+    }
+
+    /// Mint tokens burned on another chain; must be allowed by bridge. Each inbound `nonce`
+    /// can only be minted once, and amounts above the mint multisig threshold are rejected.
+    pub fn bridge_mint(&mut self, to: ActorId, value: U256, nonce: u64) -> bool {
+        if !self.get().bridges.contains(&msg::source()) {
+            panic!("Not allowed to bridge")
+        };
+        self.ensure_below_mint_threshold(value);
+        if !self.get_mut().bridge_in_nonces.insert(nonce) {
+            panic!("Nonce already processed")
+        };
+        let mutated = self.do_mint(to, self.to_shares(value));
+        self.emit_event(Event::BridgeMinted { nonce, to, value })
+            .expect("Notification Error");
+        mutated
+    }
+
+    /// Send `value` tokens of another VFT program held by this program's address to `to`,
+    /// recovering tokens sent here by mistake; requires admin rights.
+    pub async fn rescue_tokens(&mut self, token_program: ActorId, to: ActorId, value: U256) -> bool {
+        self.ensure_is_admin();
+        if token_program == exec::program_id() {
+            panic!("Cannot rescue own tokens")
+        };
+        let request = vft_io::Transfer::encode_call(to, value);
+        let reply = msg::send_bytes_for_reply(token_program, request, 0, 0)
+            .expect("Error sending message")
+            .await
+            .expect("Error in reply");
+        let mutated = vft_io::Transfer::decode_reply(reply).expect("Invalid reply");
+        if mutated {
+            self.emit_event(Event::TokensRescued { token_program, to, value })
+                .expect("Notification Error");
+        }
+        mutated
+    }
+
+    /// Apply a chunk produced by `export_state` of the old deployment. Only possible if the
+    /// program was created with import open, until `finish_import`; requires admin rights.
+    pub fn import_state(&mut self, chunk: Vec<u8>) {
+        self.ensure_is_admin();
+        if !self.get().import_open {
+            panic!("Import is finished")
+        };
+        let entries = Vec::<StateEntry>::decode(&mut chunk.as_slice()).expect("Invalid chunk");
+        let count = entries.len() as u32;
+        for entry in entries {
+            match entry {
+                StateEntry::Balance(account, balance) => {
+                    let prior = self.before_balance_change(&[account]);
+                    utils::panicking(|| {
+                        mint(Storage::balances(), Storage::total_supply(), account, balance)
+                    });
+                    self.after_balance_change(prior);
+                }
+                StateEntry::RebaseIndex(index) => {
+                    let storage = self.get_mut();
+                    storage.rebase_index = index;
+                    storage.last_accrual_block = exec::block_height();
+                }
+                StateEntry::Allowance(owner, spender, value) => {
+                    funcs::approve(Storage::allowances(), owner, spender, value);
+                }
+                StateEntry::AllowanceExpiry(owner, spender, deadline) => {
+                    self.get_mut().allowance_expiry.insert((owner, spender), deadline);
+                }
+                StateEntry::Blacklisted(account) => {
+                    self.get_mut().blacklist.insert(account);
+                }
+                StateEntry::Frozen(account, shares) => {
+                    self.get_mut().frozen.insert(account, shares);
+                }
+                StateEntry::Vesting(account, schedule) => {
+                    self.get_mut().vesting.entry(account).or_default().push(schedule);
+                }
+                StateEntry::Role(role, account) => {
+                    let storage = self.get_mut();
+                    match role {
+                        Role::Admin => storage.admins.insert(account),
+                        Role::Minter => storage.minters.insert(account),
+                        Role::Burner => storage.burners.insert(account),
+                        Role::Bridge => storage.bridges.insert(account),
+                        Role::Compliance => storage.compliance.insert(account),
+                        Role::Guardian => storage.guardians.insert(account),
+                        Role::FlashBorrower => storage.flash_borrowers.insert(account),
+                    };
+                }
+            }
+        }
+        self.emit_event(Event::StateImported { entries: count })
+            .expect("Notification Error");
+    }
+
+    /// Close `import_state` for good once the migration is done; it can't be reopened.
+    /// Requires admin rights.
+    pub fn finish_import(&mut self) {
+        self.ensure_is_admin();
+        self.get_mut().import_open = false;
+        self.emit_event(Event::ImportFinished)
+            .expect("Notification Error");
+    }
+
+    /// Move funds out of a blacklisted account, frozen ones included, e.g. to return stolen
+    /// funds; requires admin rights. Recorded in the compliance log with `reason`.
+    pub fn seize(&mut self, from: ActorId, to: ActorId, value: U256, reason: String) -> bool {
+        self.ensure_is_admin();
+        if !self.is_blacklisted(from) {
+            panic!("Account is not blacklisted")
+        };
+        self.ensure_not_blacklisted(to);
+        let shares = self.to_shares(value);
+
+        // Seized funds come out of the frozen part first
+        let remaining = self.shares_of(from).saturating_sub(shares);
+        let frozen = self.frozen_shares(from);
+        if frozen > remaining {
+            self.get_mut().frozen.insert(from, remaining);
+        }
+        let mutated = self.move_balance(from, to, shares);
+        let by = self.record_compliance(ComplianceAction::Seize { to, value }, from, &reason);
+        self.emit_event(Event::Seized { from, to, value, by, reason })
+            .expect("Notification Error");
+        mutated
+    }
+
+    /// Start moving the balance of `lost` to `new_owner` after `RECOVERY_DELAY_MS`, during
+    /// which `lost` can veto with `cancel_recovery`; requires admin rights. The program's
+    /// own escrow can't be recovered.
+    pub fn initiate_recovery(&mut self, lost: ActorId, new_owner: ActorId) {
+        self.ensure_is_admin();
+        self.ensure_not_blacklisted(new_owner);
+        if lost == new_owner {
+            panic!("Same account")
+        };
+        if lost == exec::program_id() {
+            panic!("Cannot recover the program's escrow")
+        };
+        let by = msg::source();
+        let executable_at = exec::block_timestamp().saturating_add(RECOVERY_DELAY_MS);
+        let recovery = Recovery { new_owner, initiated_by: by, executable_at };
+        if self.get_mut().recoveries.insert(lost, recovery).is_some() {
+            panic!("Recovery already pending")
+        };
+        self.emit_event(Event::RecoveryInitiated { lost, new_owner, executable_at, by })
+            .expect("Notification Error");
+    }
+
+    /// Drop the pending recovery of `lost`; allowed to `lost` itself, as a veto, and to admins.
+    pub fn cancel_recovery(&mut self, lost: ActorId) {
+        let by = msg::source();
+        if by != lost {
+            self.ensure_is_admin();
+        }
+        if self.get_mut().recoveries.remove(&lost).is_none() {
+            panic!("No pending recovery")
+        };
+        self.emit_event(Event::RecoveryCancelled { lost, by })
+            .expect("Notification Error");
+    }
+
+    /// Move the balance of `lost`, except frozen funds, and its vesting schedules to the new
+    /// owner once the veto period is over; open to anyone. Returns the amount moved. Fails
+    /// while the new owner is blacklisted.
+    pub fn execute_recovery(&mut self, lost: ActorId) -> U256 {
+        let Some(recovery) = self.get().recoveries.get(&lost).cloned() else {
+            panic!("No pending recovery")
+        };
+        if exec::block_timestamp() < recovery.executable_at {
+            panic!("Recovery is still in its veto period")
+        };
+        let new_owner = recovery.new_owner;
+        // The new owner may have been blacklisted during the veto period
+        self.ensure_not_blacklisted(new_owner);
+        let storage = self.get_mut();
+        storage.recoveries.remove(&lost);
+        if let Some(schedules) = storage.vesting.remove(&lost) {
+            storage.vesting.entry(new_owner).or_default().extend(schedules);
+        }
+
+        let shares = self.shares_of(lost).saturating_sub(self.frozen_shares(lost));
+        self.move_balance(lost, new_owner, shares);
+        let value = self.to_tokens(shares);
+        self.emit_event(Event::RecoveryExecuted { lost, new_owner, value })
+            .expect("Notification Error");
+        value
+    }
+
+    /// Lift a blacklisting; requires admin rights. Recorded in the compliance log with `reason`.
+    pub fn unblacklist(&mut self, account: ActorId, reason: String) {
+        self.ensure_is_admin();
+        if self.get_mut().blacklist.remove(&account) {
+            let by = self.record_compliance(ComplianceAction::Unblacklist, account, &reason);
+            self.emit_event(Event::Unblacklisted { account, by, reason })
+                .expect("Notification Error");
+        }
+    }
+
+    /// Charge `bps` basis points of every transfer to `collector`; `None` disables the fee.
+    /// Requires admin rights.
+    pub fn set_transfer_fee(&mut self, bps: u16, collector: Option<ActorId>) {
+        self.ensure_is_admin();
+        if bps > 10_000 {
+            panic!("Fee cannot exceed 10000 bps")
+        };
+        let storage = self.get_mut();
+        storage.transfer_fee_bps = bps;
+        storage.fee_collector = collector;
+        self.emit_event(Event::TransferFeeSet { bps, collector })
+            .expect("Notification Error");
+    }
+
+    /// Exclude an account's balance from the circulating supply, e.g. the treasury, vesting
+    /// or bridge escrow accounts; requires admin rights.
+    pub fn add_non_circulating(&mut self, account: ActorId) {
+        self.ensure_is_admin();
+        if self.get_mut().non_circulating.insert(account) {
+            self.emit_event(Event::NonCirculatingAdded { account })
+                .expect("Notification Error");
+        }
+    }
+
+    /// Count an account's balance as circulating again; requires admin rights.
+    pub fn remove_non_circulating(&mut self, account: ActorId) {
+        self.ensure_is_admin();
+        if self.get_mut().non_circulating.remove(&account) {
+            self.emit_event(Event::NonCirculatingRemoved { account })
+                .expect("Notification Error");
+        }
+    }
+
+    /// Exempt an account's transfers, sent or received, from the transfer fee, e.g. the
+    /// treasury or DEX pairs; requires admin rights.
+    pub fn add_fee_exemption(&mut self, account: ActorId) {
+        self.ensure_is_admin();
+        if self.get_mut().fee_exempt.insert(account) {
+            self.emit_event(Event::FeeExemptionAdded { account })
+                .expect("Notification Error");
+        }
+    }
+
+    /// Charge the transfer fee on an account's transfers again; requires admin rights.
+    pub fn remove_fee_exemption(&mut self, account: ActorId) {
+        self.ensure_is_admin();
+        if self.get_mut().fee_exempt.remove(&account) {
+            self.emit_event(Event::FeeExemptionRemoved { account })
+                .expect("Notification Error");
+        }
+    }
+
+    /// Burn `bps` basis points of every transfer, on top of any transfer fee; 0 disables it.
+    /// Requires admin rights.
+    pub fn set_auto_burn(&mut self, bps: u16) {
+        self.ensure_is_admin();
+        if bps > 10_000 {
+            panic!("Auto-burn cannot exceed 10000 bps")
+        };
+        self.get_mut().auto_burn_bps = bps;
+        self.emit_event(Event::AutoBurnSet { bps })
+            .expect("Notification Error");
+    }
+
+    /// Only allow transfers between whitelisted accounts while enabled; requires admin rights.
+    pub fn set_whitelist_only(&mut self, enabled: bool) {
+        self.ensure_is_admin();
+        self.get_mut().whitelist_only = enabled;
+        self.emit_event(Event::WhitelistOnlySet { enabled })
+            .expect("Notification Error");
+    }
+
+    /// Make the token non-transferable while enabled, e.g. for reputation points; minting
+    /// and burning keep working. Requires admin rights.
+    pub fn set_soulbound(&mut self, enabled: bool) {
+        self.ensure_is_admin();
+        self.get_mut().soulbound = enabled;
+        self.emit_event(Event::SoulboundSet { enabled })
+            .expect("Notification Error");
+    }
+
+    /// Add an account to the transfer whitelist; requires admin rights.
+    pub fn whitelist(&mut self, account: ActorId) {
+        self.ensure_is_admin();
+        if self.get_mut().whitelist.insert(account) {
+            self.emit_event(Event::Whitelisted { account })
+                .expect("Notification Error");
+        }
+    }
+
+    /// Remove an account from the transfer whitelist; requires admin rights.
+    pub fn unwhitelist(&mut self, account: ActorId) {
+        self.ensure_is_admin();
+        if self.get_mut().whitelist.remove(&account) {
+            self.emit_event(Event::Unwhitelisted { account })
+                .expect("Notification Error");
+        }
+    }
+
+    /// Propose another ActorId as admin; it only becomes admin once it calls `accept_admin`.
+    /// A new proposal replaces the pending one. Requires admin rights and no mint multisig,
+    /// under which admins are added through `propose_multisig_action`.
+    pub fn propose_admin(&mut self, candidate: ActorId) {
+        self.ensure_is_admin();
+        self.ensure_no_multisig();
+        self.ensure_not_blacklisted(candidate);
+        self.get_mut().pending_admin = Some(candidate);
+        self.emit_event(Event::AdminProposed { candidate })
+            .expect("Notification Error");
+    }
+    /// Accept a pending admin proposal; must be called by the proposed ActorId while no mint
+    /// multisig is enabled.
+    pub fn accept_admin(&mut self) {
+        let admin = msg::source();
+        self.ensure_no_multisig();
+        let storage = self.get_mut();
+        if storage.pending_admin != Some(admin) {
+            panic!("Not the proposed admin")
+        };
+        self.ensure_not_blacklisted(admin);
+        storage.pending_admin = None;
+        if storage.admins.insert(admin) {
+            self.emit_role_change(Role::Admin, admin, true);
+        }
+        self.emit_event(Event::AdminAccepted { admin })
+            .expect("Notification Error");
+    }
+    /// Grant minter role; requires admin rights and no mint multisig, so a single admin
+    /// can't hand out minting around the threshold.
+    pub fn grant_minter_role(&mut self, to: ActorId) {
+        self.ensure_is_admin();
+        self.ensure_no_multisig();
+        self.ensure_not_blacklisted(to);
+        self.get_mut().minter_expiry.remove(&to);
+        if self.get_mut().minters.insert(to) {
+            self.emit_role_change(Role::Minter, to, true);
+        }
+    }
+
+    /// Grant minter role until `deadline` (block timestamp, ms), after which mints by `to`
+    /// are rejected; requires admin rights and no mint multisig.
+    pub fn grant_minter_until(&mut self, to: ActorId, deadline: u64) {
+        self.ensure_is_admin();
+        self.ensure_no_multisig();
+        self.ensure_not_blacklisted(to);
+        if deadline <= exec::block_timestamp() {
+            panic!("Deadline passed")
+        };
+        let storage = self.get_mut();
+        storage.minter_expiry.insert(to, deadline);
+        if storage.minters.insert(to) {
+            self.emit_role_change(Role::Minter, to, true);
+        }
+        self.emit_event(Event::MinterGrantedUntil { account: to, deadline })
+            .expect("Notification Error");
+    }
+    /// Grant burner role; requires admin rights.
+    pub fn grant_burner_role(&mut self, to: ActorId) {
+        self.ensure_is_admin();
+        self.ensure_not_blacklisted(to);
+        if self.get_mut().burners.insert(to) {
+            self.emit_role_change(Role::Burner, to, true);
+        }
+    }
+
+    /// Grant bridge role; requires admin rights.
+    pub fn grant_bridge_role(&mut self, to: ActorId) {
+        self.ensure_is_admin();
+        self.ensure_not_blacklisted(to);
+        if self.get_mut().bridges.insert(to) {
+            self.emit_role_change(Role::Bridge, to, true);
+        }
+    }
+
+    /// Grant compliance role; requires admin rights.
+    pub fn grant_compliance_role(&mut self, to: ActorId) {
+        self.ensure_is_admin();
+        self.ensure_not_blacklisted(to);
+        if self.get_mut().compliance.insert(to) {
+            self.emit_role_change(Role::Compliance, to, true);
+        }
+    }
+
+    /// Grant guardian role, which can only pause; requires admin rights.
+    pub fn grant_guardian_role(&mut self, to: ActorId) {
+        self.ensure_is_admin();
+        self.ensure_not_blacklisted(to);
+        if self.get_mut().guardians.insert(to) {
+            self.emit_role_change(Role::Guardian, to, true);
+        }
+    }
+
+    /// Grant flash borrower role, which can `flash_mint`; requires admin rights.
+    pub fn grant_flash_borrower_role(&mut self, to: ActorId) {
+        self.ensure_is_admin();
+        self.ensure_not_blacklisted(to);
+        if self.get_mut().flash_borrowers.insert(to) {
+            self.emit_role_change(Role::FlashBorrower, to, true);
+        }
+    }
+
+    /// Revoke admin role; requires admin rights and no mint multisig, under which admins are
+    /// removed through `propose_multisig_action`. The last admin cannot be revoked.
+    pub fn revoke_admin_role(&mut self, from: ActorId) {
+        self.ensure_is_admin();
+        self.ensure_no_multisig();
+        self.execute_multisig_action(MultisigAction::RemoveAdmin(from));
+    }
+    /// Give up the caller's admin role. If it was the last admin the contract is left
+    /// without admins for good and any pending proposal is dropped. Under a mint multisig,
+    /// enough admins must remain to approve.
+    pub fn renounce_admin_and_lock(&mut self) {
+        self.ensure_is_admin();
+        let admin = msg::source();
+        self.ensure_enough_signers(self.get().admins.len() - 1);
+        let storage = self.get_mut();
+        storage.admins.remove(&admin);
+        self.emit_role_change(Role::Admin, admin, false);
+        let locked = storage.admins.is_empty();
+        if locked {
+            storage.pending_admin = None;
+        }
+        self.emit_event(Event::AdminRenounced { admin, locked })
+            .expect("Notification Error");
+    }
+    /// Revoke minter role; requires admin rights.
+    pub fn revoke_minter_role(&mut self, from: ActorId) {
+        self.ensure_is_admin();
+        self.get_mut().minter_expiry.remove(&from);
+        if self.get_mut().minters.remove(&from) {
+            self.emit_role_change(Role::Minter, from, false);
+        }
+    }
+    /// Revoke burner role; requires admin rights.
+    pub fn revoke_burner_role(&mut self, from: ActorId) {
+        self.ensure_is_admin();
+        if self.get_mut().burners.remove(&from) {
+            self.emit_role_change(Role::Burner, from, false);
+        }
+    }
+    /// Revoke bridge role; requires admin rights.
+    pub fn revoke_bridge_role(&mut self, from: ActorId) {
+        self.ensure_is_admin();
+        if self.get_mut().bridges.remove(&from) {
+            self.emit_role_change(Role::Bridge, from, false);
+        }
+    }
+
+    /// Revoke compliance role; requires admin rights.
+    pub fn revoke_compliance_role(&mut self, from: ActorId) {
+        self.ensure_is_admin();
+        if self.get_mut().compliance.remove(&from) {
+            self.emit_role_change(Role::Compliance, from, false);
+        }
+    }
+
+    /// Revoke guardian role; requires admin rights.
+    pub fn revoke_guardian_role(&mut self, from: ActorId) {
+        self.ensure_is_admin();
+        if self.get_mut().guardians.remove(&from) {
+            self.emit_role_change(Role::Guardian, from, false);
+        }
+    }
+
+    /// Revoke flash borrower role; requires admin rights.
+    pub fn revoke_flash_borrower_role(&mut self, from: ActorId) {
+        self.ensure_is_admin();
+        if self.get_mut().flash_borrowers.remove(&from) {
+            self.emit_role_change(Role::FlashBorrower, from, false);
+        }
+    }
+
+    /// Assign a KYC tier to an account; must be allowed by compliance. Accounts start at tier 0.
+    pub fn set_tier(&mut self, account: ActorId, tier: u8) {
+        if !self.get().compliance.contains(&msg::source()) {
+            panic!("Not compliance")
+        };
+        let storage = self.get_mut();
+        if tier == 0 {
+            storage.tiers.remove(&account);
+        } else {
+            storage.tiers.insert(account, tier);
+        }
+        self.emit_event(Event::TierSet { account, tier })
+            .expect("Notification Error");
+    }
+
+    /// Set or clear (`None`) the rule for accounts of `tier`; must be allowed by compliance.
+    pub fn set_tier_rule(&mut self, tier: u8, rule: Option<TierRule>) {
+        if !self.get().compliance.contains(&msg::source()) {
+            panic!("Not compliance")
+        };
+        if let Some((_, period_blocks)) = rule.as_ref().and_then(|rule| rule.max_per_period) {
+            if period_blocks == 0 {
+                panic!("Period must be positive")
+            };
+        }
+        let storage = self.get_mut();
+        match rule.clone() {
+            Some(rule) => storage.tier_rules.insert(tier, rule),
+            None => storage.tier_rules.remove(&tier),
+        };
+        self.emit_event(Event::TierRuleSet { tier, rule })
+            .expect("Notification Error");
+    }
+
+    /// List all minters
+    pub fn minters(&self) -> Vec<ActorId> {
+        self.get().minters.clone().into_iter().collect()
+    }
+    /// Whether a `mint_with_id` operation id was already processed
+    pub fn is_mint_processed(&self, operation_id: H256) -> bool {
+        self.get().processed_mint_ids.contains(&operation_id)
+    }
+    /// What `minter` minted with `mint` and `mint_batch`
+    pub fn minter_stats(&self, minter: ActorId) -> MinterStats {
+        self.get().minter_stats.get(&minter).cloned().unwrap_or_default()
+    }
+    /// Deadline of a time-limited minter, if its role expires
+    pub fn minter_expiry(&self, account: ActorId) -> Option<u64> {
+        self.get().minter_expiry.get(&account).copied()
+    }
+    /// List all burners
+    pub fn burners(&self) -> Vec<ActorId> {
+        self.get().burners.clone().into_iter().collect()
+    }
+    /// List all guardians
+    pub fn guardians(&self) -> Vec<ActorId> {
+        self.get().guardians.clone().into_iter().collect()
+    }
+    /// List all compliance officers
+    pub fn compliance(&self) -> Vec<ActorId> {
+        self.get().compliance.clone().into_iter().collect()
+    }
+    /// KYC tier of an account
+    pub fn tier_of(&self, account: ActorId) -> u8 {
+        self.get().tiers.get(&account).copied().unwrap_or_default()
+    }
+    /// Rule applying to accounts of `tier`, if any
+    pub fn tier_rule(&self, tier: u8) -> Option<TierRule> {
+        self.get().tier_rules.get(&tier).cloned()
+    }
+    /// List all bridges
+    pub fn bridges(&self) -> Vec<ActorId> {
+        self.get().bridges.clone().into_iter().collect()
+    }
+    /// Whether inbound bridge `nonce` was already minted
+    pub fn is_bridge_nonce_processed(&self, nonce: u64) -> bool {
+        self.get().bridge_in_nonces.contains(&nonce)
+    }
+    /// List all admins
+    pub fn admins(&self) -> Vec<ActorId> {
+        self.get().admins.clone().into_iter().collect()
+    }
+    /// Admin proposed with `propose_admin` and not accepted yet
+    pub fn pending_admin(&self) -> Option<ActorId> {
+        self.get().pending_admin
+    }
+
+    /// Whether transfers, mints and burns are paused
+    pub fn is_paused(&self) -> bool {
+        self.get().paused
+    }
+
+    /// What `account` can still send in the current period; `None` if it has no limit
+    pub fn remaining_spend(&self, account: ActorId) -> Option<U256> {
+        let limit = self.get().spending_limits.get(&account)?;
+        Some(limit.limit.saturating_sub(limit.spent_at(exec::block_height())))
+    }
+    /// Pending recovery of a lost account, if any
+    pub fn pending_recovery(&self, lost: ActorId) -> Option<Recovery> {
+        self.get().recoveries.get(&lost).cloned()
+    }
+    /// Frozen part of an account's balance
+    pub fn frozen_of(&self, account: ActorId) -> U256 {
+        self.to_tokens(self.frozen_shares(account))
+    }
+    /// Native value `account` deposited and has not withdrawn yet
+    pub fn wrapped_of(&self, account: ActorId) -> U256 {
+        self.get().wrapped.get(&account).copied().unwrap_or_default()
+    }
+    /// Token balance of `account`: its shares scaled by the rebase index
+    pub fn balance_of(&self, account: ActorId) -> U256 {
+        self.to_tokens(self.shares_of(account))
+    }
+    /// Total token supply: all shares scaled by the rebase index
+    pub fn total_supply(&self) -> U256 {
+        self.to_tokens(*Storage::total_supply())
+    }
+    /// Shares held by `account`, the unit balances are stored in
+    pub fn shares_of(&self, account: ActorId) -> U256 {
+        funcs::balance_of(Storage::balances(), account)
+    }
+    /// Tokens per share, scaled by `REBASE_PRECISION`, including interest not yet accrued.
+    /// Interest stops once the total supply reaches the max supply.
+    pub fn index(&self) -> U256 {
+        let storage = self.get();
+        let index = match storage.rebase_index {
+            index if index.is_zero() => U256::from(REBASE_PRECISION),
+            index => index,
+        };
+        if storage.interest_rate_ppm == 0 {
+            return index;
+        }
+        let elapsed = exec::block_height().saturating_sub(storage.last_accrual_block);
+        let growth = storage.interest_rate_ppm as u64 * elapsed as u64;
+        let grown = index.saturating_mul(U256::from(1_000_000u64).saturating_add(U256::from(growth)))
+            / U256::from(1_000_000u64);
+        let shares = *Storage::total_supply();
+        match storage.max_supply {
+            Some(max_supply) if !shares.is_zero() => {
+                let cap = max_supply.saturating_mul(U256::from(REBASE_PRECISION)) / shares;
+                grown.min(cap.max(index))
+            }
+            _ => grown,
+        }
+    }
+    /// Interest accrued per block, in parts per million
+    pub fn interest_rate(&self) -> u32 {
+        self.get().interest_rate_ppm
+    }
+    /// Whether an account is blacklisted
+    pub fn is_blacklisted(&self, account: ActorId) -> bool {
+        self.get().blacklist.contains(&account)
+    }
+    /// Blacklisted accounts ordered by account, plus the total number of them
+    pub fn blacklisted_page(&self, start: u32, limit: u32) -> (Vec<ActorId>, u32) {
+        let mut accounts: Vec<ActorId> = self.get().blacklist.iter().copied().collect();
+        accounts.sort_unstable();
+        paginate(accounts.into_iter(), start, limit, MAX_PAGE_SIZE)
+    }
+
+    /// Total supply minus the balances of non-circulating accounts
+    pub fn circulating_supply(&self) -> U256 {
+        let excluded = self
+            .get()
+            .non_circulating
+            .iter()
+            .fold(U256::zero(), |acc, account| acc.saturating_add(self.balance_of(*account)));
+        self.total_supply().saturating_sub(excluded)
+    }
+    /// Accounts excluded from the circulating supply, ordered by account
+    pub fn non_circulating(&self) -> Vec<ActorId> {
+        let mut accounts: Vec<ActorId> = self.get().non_circulating.iter().copied().collect();
+        accounts.sort_unstable();
+        accounts
+    }
+    /// Whether an account's transfers skip the transfer fee
+    pub fn is_fee_exempt(&self, account: ActorId) -> bool {
+        self.get().fee_exempt.contains(&account)
+    }
+    /// Accounts exempt from the transfer fee, ordered by account
+    pub fn fee_exempt(&self) -> Vec<ActorId> {
+        let mut accounts: Vec<ActorId> = self.get().fee_exempt.iter().copied().collect();
+        accounts.sort_unstable();
+        accounts
+    }
+    /// Transfer fee in basis points and the account collecting it
+    pub fn transfer_fee(&self) -> (u16, Option<ActorId>) {
+        let storage = self.get();
+        (storage.transfer_fee_bps, storage.fee_collector)
+    }
+
+    /// Flash mint fee in basis points
+    pub fn flash_fee_bps(&self) -> u16 {
+        self.get().flash_fee_bps
+    }
+    /// List all flash borrowers
+    pub fn flash_borrowers(&self) -> Vec<ActorId> {
+        self.get().flash_borrowers.clone().into_iter().collect()
+    }
+
+    /// Share of every transfer burned, in basis points
+    pub fn auto_burn_bps(&self) -> u16 {
+        self.get().auto_burn_bps
+    }
+    /// Cumulative amount burned by auto-burn on transfers
+    pub fn auto_burned(&self) -> U256 {
+        self.get().auto_burned
+    }
+
+    /// Whether transfers are restricted to whitelisted accounts
+    pub fn is_whitelist_only(&self) -> bool {
+        self.get().whitelist_only
+    }
+    /// Registered transfer hook programs
+    pub fn transfer_hooks(&self) -> TransferHooks {
+        self.get().transfer_hooks.clone()
+    }
+    /// Token name, as last set by `set_name`
+    pub fn name(&self) -> String {
+        match &self.get().name {
+            Some(name) => name.clone(),
+            None => self.vft.name().into(),
+        }
+    }
+    /// Token symbol, as last set by `set_symbol`
+    pub fn symbol(&self) -> String {
+        match &self.get().symbol {
+            Some(symbol) => symbol.clone(),
+            None => self.vft.symbol().into(),
+        }
+    }
+    /// Token icon URI, if any
+    pub fn icon_uri(&self) -> Option<String> {
+        self.get().icon_uri.clone()
+    }
+    /// Logo URI, project URL and description in a single call
+    pub fn extended_metadata(&self) -> ExtendedMetadata {
+        let storage = self.get();
+        ExtendedMetadata {
+            logo_uri: storage.icon_uri.clone(),
+            project_url: storage.project_url.clone(),
+            description: storage.description.clone(),
+        }
+    }
+    /// Whether the token is non-transferable
+    pub fn is_soulbound(&self) -> bool {
+        self.get().soulbound
+    }
+    /// Whether an account is on the transfer whitelist
+    pub fn is_whitelisted(&self, account: ActorId) -> bool {
+        self.get().whitelist.contains(&account)
+    }
+    /// Whitelisted accounts ordered by account, plus the total number of them
+    pub fn whitelisted_page(&self, start: u32, limit: u32) -> (Vec<ActorId>, u32) {
+        let mut accounts: Vec<ActorId> = self.get().whitelist.iter().copied().collect();
+        accounts.sort_unstable();
+        paginate(accounts.into_iter(), start, limit, MAX_PAGE_SIZE)
+    }
+
+    /// Allowance of `spender` over `owner`'s tokens; zero once expired
+    pub fn allowance(&self, owner: ActorId, spender: ActorId) -> U256 {
+        self.to_tokens(self.allowance_shares(owner, spender))
+    }
+    /// Balances of up to `MAX_PAGE_SIZE` accounts, in the order given
+    pub fn balances_of(&self, accounts: Vec<ActorId>) -> Vec<U256> {
+        if accounts.len() > MAX_PAGE_SIZE as usize {
+            panic!("Too many accounts")
+        };
+        accounts.into_iter().map(|account| self.balance_of(account)).collect()
+    }
+    /// Allowances of up to `MAX_PAGE_SIZE` (owner, spender) pairs, in the order given
+    pub fn allowances_of(&self, pairs: Vec<(ActorId, ActorId)>) -> Vec<U256> {
+        if pairs.len() > MAX_PAGE_SIZE as usize {
+            panic!("Too many pairs")
+        };
+        pairs
+            .into_iter()
+            .map(|(owner, spender)| self.allowance(owner, spender))
+            .collect()
+    }
+    /// Deadline of an allowance, if it expires
+    pub fn allowance_expiry(&self, owner: ActorId, spender: ActorId) -> Option<u64> {
+        self.get().allowance_expiry.get(&(owner, spender)).copied()
+    }
+
+    /// Nonce the next permit of `owner` must be signed with
+    pub fn permit_nonce(&self, owner: ActorId) -> u64 {
+        self.get().permit_nonces.get(&owner).copied().unwrap_or(0)
+    }
+    /// Bytes `owner` has to sign for a `permit` with its current nonce
+    pub fn permit_message(&self, owner: ActorId, spender: ActorId, value: U256, deadline: u64) -> Vec<u8> {
+        PermitMessage {
+            program: exec::program_id(),
+            owner,
+            spender,
+            value,
+            nonce: self.permit_nonce(owner),
+            deadline,
+        }
+        .encode()
+    }
+
+    /// Amount that can still be minted before reaching the supply cap; `None` if uncapped
+    pub fn mintable_remaining(&self) -> Option<U256> {
+        self.get()
+            .max_supply
+            .map(|max_supply| max_supply.saturating_sub(self.total_supply()))
+    }
+
+    /// Balance of `account` when snapshot `snapshot_id` was taken
+    pub fn balance_of_at(&self, account: ActorId, snapshot_id: u64) -> U256 {
+        self.ensure_valid_snapshot(snapshot_id);
+        let snapshots = self.get().account_snapshots.get(&account);
+        let shares = snapshot_value(snapshots.map_or(&[][..], |s| s.as_slice()), snapshot_id)
+            .unwrap_or_else(|| funcs::balance_of(Storage::balances(), account));
+        self.to_tokens_at(shares, snapshot_id)
+    }
+    /// Total supply when snapshot `snapshot_id` was taken
+    pub fn total_supply_at(&self, snapshot_id: u64) -> U256 {
+        self.ensure_valid_snapshot(snapshot_id);
+        let shares = snapshot_value(&self.get().supply_snapshots, snapshot_id)
+            .unwrap_or_else(|| *Storage::total_supply());
+        self.to_tokens_at(shares, snapshot_id)
+    }
+    /// Account `account` delegates its voting power to, if any
+    pub fn delegates(&self, account: ActorId) -> Option<ActorId> {
+        self.get().delegates.get(&account).copied()
+    }
+    /// Voting power currently delegated to `account`
+    pub fn get_votes(&self, account: ActorId) -> U256 {
+        self.to_tokens(self.vote_shares(account))
+    }
+    /// Voting power delegated to `account` at the end of block `block`. Votes are checkpointed
+    /// in shares and valued at the current rebase index.
+    pub fn get_votes_at(&self, account: ActorId, block: u32) -> U256 {
+        let Some(checkpoints) = self.get().vote_checkpoints.get(&account) else {
+            return U256::zero();
+        };
+        let index = checkpoints.partition_point(|(height, _)| *height <= block);
+        match index {
+            0 => U256::zero(),
+            _ => self.to_tokens(checkpoints[index - 1].1),
+        }
+    }
+    /// Pending time locks sent or received by `account`, ordered by id
+    pub fn pending_locks(&self, account: ActorId) -> Vec<(u64, TimeLock)> {
+        let mut locks: Vec<(u64, TimeLock)> = self
+            .get()
+            .locks
+            .iter()
+            .filter(|(_, lock)| lock.from == account || lock.to == account)
+            .map(|(id, lock)| (*id, lock.clone()))
+            .collect();
+        locks.sort_unstable_by_key(|(id, _)| *id);
+        locks
+    }
+    /// Stream `id`, if it is still running or has something left to withdraw
+    pub fn stream(&self, stream_id: u64) -> Option<Stream> {
+        self.get().streams.get(&stream_id).cloned()
+    }
+    /// Amount the recipient of stream `id` can withdraw now
+    pub fn withdrawable_from_stream(&self, stream_id: u64) -> U256 {
+        self.get()
+            .streams
+            .get(&stream_id)
+            .map_or(U256::zero(), |stream| {
+                self.to_tokens(stream.streamed(exec::block_height()) - stream.withdrawn)
+            })
+    }
+    /// Native value `account` can claim with `claim_dividend`
+    pub fn unclaimed(&self, account: ActorId) -> u128 {
+        (0..self.get().dividends.len() as u32)
+            .map(|id| self.dividend_share(id, account))
+            .sum()
+    }
+    /// Dividends deposited so far, indexed by id
+    pub fn dividends(&self) -> Vec<Dividend> {
+        self.get().dividends.clone()
+    }
+    /// Id of the latest snapshot; 0 if none was taken
+    pub fn current_snapshot_id(&self) -> u64 {
+        self.get().snapshot_id
+    }
+
+    /// Vested tokens of `account` that `release` would unlock now
+    pub fn claimable(&self, account: ActorId) -> U256 {
+        let now = exec::block_timestamp();
+        let shares = self
+            .vesting_schedules(account)
+            .iter()
+            .fold(U256::zero(), |acc, schedule| {
+                acc.saturating_add(schedule.vested(now) - schedule.released)
+            });
+        self.to_tokens(shares)
+    }
+    /// Tokens of `account` that are still locked, vested or not
+    pub fn locked_balance(&self, account: ActorId) -> U256 {
+        self.to_tokens(self.locked_shares(account))
+    }
+    /// Vesting schedules of `account` that aren't fully released
+    pub fn vesting_schedules(&self, account: ActorId) -> Vec<VestingSchedule> {
+        self.get().vesting.get(&account).cloned().unwrap_or_default()
+    }
+
+    /// Whether airdrop leaf `index` was claimed
+    pub fn is_claimed(&self, index: u32) -> bool {
+        let word = self.get().airdrop_bitmap.get(&(index / 64)).copied().unwrap_or(0);
+        word & (1 << (index % 64)) != 0
+    }
+    /// Merkle root, total allocation and amount claimed of the current airdrop
+    pub fn airdrop(&self) -> Option<([u8; 32], U256, U256)> {
+        let storage = self.get();
+        storage
+            .airdrop_root
+            .map(|root| (root, storage.airdrop_total, storage.airdrop_claimed))
+    }
+
+    /// Up to `limit` of the latest `MAX_SUPPLY_HISTORY` supply changes made at or after
+    /// `from_block`, oldest first
+    pub fn supply_history(&self, from_block: u32, limit: u32) -> Vec<SupplyChange> {
+        let storage = self.get();
+        let (newer, older) = storage.supply_history.split_at(storage.supply_history_next);
+        older
+            .iter()
+            .chain(newer.iter())
+            .filter(|change| change.block >= from_block)
+            .take(limit.min(MAX_PAGE_SIZE) as usize)
+            .cloned()
+            .collect()
+    }
+
+    /// Burns with a recorded reason, oldest first, starting at `cursor`, plus the number of
+    /// entries kept
+    pub fn burn_log(&self, cursor: u32, limit: u32) -> (Vec<BurnRecord>, u32) {
+        let storage = self.get();
+        let (newer, older) = storage.burn_log.split_at(storage.burn_log_next);
+        paginate(older.iter().chain(newer.iter()).cloned(), cursor, limit, MAX_PAGE_SIZE)
+    }
+
+    /// Current emission schedule, if any
+    pub fn emission_schedule(&self) -> Option<EmissionSchedule> {
+        self.get().emission.clone()
+    }
+    /// Emissions `drip` would mint now, before the max supply cap
+    pub fn pending_emissions(&self) -> U256 {
+        match &self.get().emission {
+            Some(schedule) => schedule.emitted(schedule.dripped_until, exec::block_height()),
+            None => U256::zero(),
+        }
+    }
+    /// Faucet settings, if enabled
+    pub fn faucet_config(&self) -> Option<FaucetConfig> {
+        self.get().faucet.clone()
+    }
+    /// Block from which an account can use the faucet again, if it used it before
+    pub fn faucet_available_at(&self, account: ActorId) -> Option<u32> {
+        let storage = self.get();
+        let cooldown = storage.faucet.as_ref().map_or(0, |config| config.cooldown_blocks);
+        let last = storage.faucet_last_claim.get(&account)?;
+        Some(last.saturating_add(cooldown))
+    }
+    /// Blacklistings, freezes and seizures, oldest first, starting at `cursor`, plus the
+    /// total number of entries. The log is append-only.
+    pub fn compliance_log(&self, cursor: u32, limit: u32) -> (Vec<ComplianceRecord>, u32) {
+        paginate(self.get().compliance_log.iter().cloned(), cursor, limit, MAX_PAGE_SIZE)
+    }
+
+    /// Mint multisig threshold and required approvals, if enabled
+    pub fn mint_multisig(&self) -> Option<(U256, u32)> {
+        self.get().mint_multisig
+    }
+    /// Pending multisig proposals ordered by id
+    pub fn proposals(&self) -> Vec<(u64, MultisigProposal)> {
+        let mut proposals: Vec<(u64, MultisigProposal)> = self
+            .get()
+            .proposals
+            .iter()
+            .map(|(id, proposal)| (*id, proposal.clone()))
+            .collect();
+        proposals.sort_unstable_by_key(|(id, _)| *id);
+        proposals
+    }
+    /// Pending scheduled mints ordered by id
+    pub fn scheduled_mints(&self) -> Vec<(u64, ScheduledMint)> {
+        let mut schedules: Vec<(u64, ScheduledMint)> = self
+            .get()
+            .scheduled_mints
+            .iter()
+            .map(|(id, schedule)| (*id, schedule.clone()))
+            .collect();
+        schedules.sort_unstable_by_key(|(id, _)| *id);
+        schedules
+    }
+    /// Mints and burns on an account, oldest first, starting at `cursor`, plus the number
+    /// of entries kept
+    pub fn account_history(&self, account: ActorId, cursor: u32, limit: u32) -> (Vec<AccountOperation>, u32) {
+        let history = self.get().account_history.get(&account);
+        paginate(history.into_iter().flatten().cloned(), cursor, limit, MAX_PAGE_SIZE)
+    }
+    /// Cumulative amount ever minted
+    pub fn total_minted(&self) -> U256 {
+        self.get().total_minted
+    }
+    /// Cumulative amount ever burned
+    pub fn total_burned(&self) -> U256 {
+        self.get().total_burned
+    }
+    /// Cumulative amount burned by calls from `burner`, whatever account the tokens came
+    /// from; auto-burns on transfers are not counted
+    pub fn burned_by(&self, burner: ActorId) -> U256 {
+        self.get().burned_by.get(&burner).copied().unwrap_or_default()
+    }
+    /// Up to `MAX_PAGE_SIZE` callers that burned the most with their cumulative amounts,
+    /// largest first
+    pub fn burner_stats(&self) -> Vec<(ActorId, U256)> {
+        let mut stats: Vec<(ActorId, U256)> = self
+            .get()
+            .burned_by
+            .iter()
+            .map(|(burner, burned)| (*burner, *burned))
+            .collect();
+        stats.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        stats.truncate(MAX_PAGE_SIZE as usize);
+        stats
+    }
+    /// Number of accounts holding a non-zero balance
+    pub fn holder_count(&self) -> u32 {
+        Storage::balances().len() as u32
+    }
+    /// Holders with their balances ordered by account, plus the total number of holders
+    pub fn holders_page(&self, start: u32, limit: u32) -> (Vec<(ActorId, U256)>, u32) {
+        let holders = self.get().holder_accounts.iter().map(|account| self.holder(*account));
+        paginate(holders, start, limit, MAX_PAGE_SIZE)
+    }
+    /// Up to `limit` holders ordered by account, starting after `cursor`; pass the last
+    /// account of a page as the cursor of the next one
+    pub fn holders(&self, cursor: Option<ActorId>, limit: u32) -> Vec<(ActorId, U256)> {
+        let start = match cursor {
+            Some(cursor) => Bound::Excluded(cursor),
+            None => Bound::Unbounded,
+        };
+        self.get()
+            .holder_accounts
+            .range((start, Bound::Unbounded))
+            .take(limit.min(MAX_PAGE_SIZE) as usize)
+            .map(|account| self.holder(*account))
+            .collect()
+    }
+    /// Up to `MAX_PAGE_SIZE` holders with accounts between `from` and `to` inclusive,
+    /// ordered by account
+    pub fn holders_in_range(&self, from: ActorId, to: ActorId) -> Vec<(ActorId, U256)> {
+        if from > to {
+            return Vec::new();
+        }
+        self.get()
+            .holder_accounts
+            .range(from..=to)
+            .take(MAX_PAGE_SIZE as usize)
+            .map(|account| self.holder(*account))
+            .collect()
+    }
+    /// Up to `n` (at most `MAX_PAGE_SIZE`) largest holders with their balances, largest first
+    pub fn top_holders(&self, n: u32) -> Vec<(ActorId, U256)> {
+        self.get()
+            .balance_index
+            .iter()
+            .rev()
+            .take(n.min(MAX_PAGE_SIZE) as usize)
+            .map(|(shares, account)| (*account, self.to_tokens(*shares)))
+            .collect()
+    }
+    /// Holders with a balance above `threshold`, largest first, starting at `cursor`, plus
+    /// the total number of such holders
+    pub fn balances_above(&self, threshold: U256, cursor: u32, limit: u32) -> (Vec<(ActorId, U256)>, u32) {
+        let above = self
+            .get()
+            .balance_index
+            .range((Bound::Excluded((self.to_shares(threshold), ActorId::from([u8::MAX; 32]))), Bound::Unbounded))
+            .rev()
+            .map(|(shares, account)| (*account, self.to_tokens(*shares)))
+            .filter(|(_, balance)| *balance > threshold);
+        paginate(above, cursor, limit, MAX_PAGE_SIZE)
+    }
+    /// SCALE-encoded `Vec<StateEntry>` with up to `limit` entries starting at `cursor`, plus
+    /// the cursor of the next chunk if any: the rebase index, balances, allowances and their
+    /// expiries, roles, the blacklist, frozen amounts and vesting schedules. Balances,
+    /// allowances and frozen amounts are in shares, as stored, valued by the exported index.
+    /// This program's own escrow balance is left out, so settle pending locks and streams
+    /// before migrating.
+    pub fn export_state(&self, cursor: u32, limit: u32) -> (Vec<u8>, Option<u32>) {
+        let storage = self.get();
+        let program = exec::program_id();
+        let balances = storage
+            .holder_accounts
+            .iter()
+            .filter(|account| **account != program)
+            .map(|account| (*account, self.shares_of(*account)));
+        let mut allowances: Vec<((ActorId, ActorId), U256)> =
+            Storage::allowances().iter().map(|(key, value)| (*key, *value)).collect();
+        allowances.sort_unstable_by_key(|(key, _)| *key);
+        let mut expiries: Vec<((ActorId, ActorId), u64)> =
+            storage.allowance_expiry.iter().map(|(key, deadline)| (*key, *deadline)).collect();
+        expiries.sort_unstable_by_key(|(key, _)| *key);
+        let mut blacklist: Vec<ActorId> = storage.blacklist.iter().copied().collect();
+        blacklist.sort_unstable();
+        let mut frozen: Vec<(ActorId, U256)> =
+            storage.frozen.iter().map(|(account, shares)| (*account, *shares)).collect();
+        frozen.sort_unstable_by_key(|(account, _)| *account);
+        let mut vesting: Vec<(ActorId, VestingSchedule)> = storage
+            .vesting
+            .iter()
+            .flat_map(|(account, schedules)| {
+                schedules.iter().map(|schedule| (*account, schedule.clone()))
+            })
+            .collect();
+        vesting.sort_by_key(|(account, _)| *account);
+        let mut roles: Vec<(Role, ActorId)> = Vec::new();
+        for (role, accounts) in [
+            (Role::Admin, &storage.admins),
+            (Role::Minter, &storage.minters),
+            (Role::Burner, &storage.burners),
+            (Role::Bridge, &storage.bridges),
+            (Role::Compliance, &storage.compliance),
+            (Role::Guardian, &storage.guardians),
+            (Role::FlashBorrower, &storage.flash_borrowers),
+        ] {
+            let mut accounts: Vec<ActorId> = accounts.iter().copied().collect();
+            accounts.sort_unstable();
+            roles.extend(accounts.into_iter().map(|account| (role, account)));
+        }
+
+        let entries = core::iter::once(StateEntry::RebaseIndex(self.index()))
+            .chain(balances.map(|(account, balance)| StateEntry::Balance(account, balance)))
+            .chain(
+                allowances
+                    .into_iter()
+                    .map(|((owner, spender), value)| StateEntry::Allowance(owner, spender, value)),
+            )
+            .chain(expiries.into_iter().map(|((owner, spender), deadline)| {
+                StateEntry::AllowanceExpiry(owner, spender, deadline)
+            }))
+            .chain(roles.into_iter().map(|(role, account)| StateEntry::Role(role, account)))
+            .chain(blacklist.into_iter().map(StateEntry::Blacklisted))
+            .chain(frozen.into_iter().map(|(account, shares)| StateEntry::Frozen(account, shares)))
+            .chain(vesting.into_iter().map(|(account, schedule)| StateEntry::Vesting(account, schedule)));
+        let (chunk, total) = paginate(entries, cursor, limit, MAX_PAGE_SIZE);
+        let next = cursor.saturating_add(chunk.len() as u32);
+        (chunk.encode(), (next < total).then_some(next))
+    }
+    /// Accounts holding a non-zero balance below `threshold`, ordered by account
+    pub fn dust_accounts(&self, threshold: U256) -> Vec<ActorId> {
+        let mut accounts: Vec<ActorId> = self
+            .get()
+            .balance_index
+            .range(..(self.to_shares(threshold), ActorId::zero()))
+            .map(|(_, account)| *account)
+            .collect();
+        accounts.sort_unstable();
+        accounts
+    }
+    /// Audit check that the total supply equals the sum of all balances and that every
+    /// holder is indexed. O(holders), meant for occasional checks after upgrades or imports
+    /// rather than hot paths.
+    pub fn verify_supply_invariant(&self) -> bool {
+        let index = &self.get().balance_index;
+        let sum = index
+            .iter()
+            .try_fold(U256::zero(), |acc, (balance, _)| acc.checked_add(*balance));
+        index.len() == Storage::balances().len() && sum == Some(*Storage::total_supply())
+    }
+    /// Holder count, supply totals and the largest balance in a single call
+    pub fn stats(&self) -> Stats {
+        let storage = self.get();
+        Stats {
+            holders: self.holder_count(),
+            total_supply: self.total_supply(),
+            total_minted: storage.total_minted,
+            total_burned: storage.total_burned,
+            largest_balance: storage
+                .balance_index
+                .last()
+                .map_or(U256::zero(), |(shares, _)| self.to_tokens(*shares)),
+        }
+    }
+    /// Total supply, holder count, minted and burned totals in a single call
+    pub fn summary(&self) -> SupplySummary {
+        SupplySummary {
+            total_supply: self.total_supply(),
+            holders: self.holder_count(),
+            total_minted: self.get().total_minted,
+            total_burned: self.get().total_burned,
+        }
+    }
+}
+
+impl ExtendedService {
+    fn ensure_is_admin(&self) {
+        if !self.get().admins.contains(&msg::source()) {
+            panic!("Not admin")
+        };
+    }
+
+    fn ensure_is_minter(&self) {
+        if let Some(reason) = self.minter_blocked(msg::source()) {
+            panic!("{}", reason)
+        };
+    }
+
+    /// Why `account` currently can't mint as a minter, if it can't.
+    fn minter_blocked(&self, account: ActorId) -> Option<&'static str> {
+        let storage = self.get();
+        if !storage.minters.contains(&account) {
+            return Some("Not allowed to mint");
+        }
+        if storage
+            .minter_expiry
+            .get(&account)
+            .is_some_and(|deadline| exec::block_timestamp() > *deadline)
+        {
+            return Some("Minter role expired");
+        }
+        None
+    }
+
+    fn emit_role_change(&mut self, role: Role, account: ActorId, granted: bool) {
+        let by = msg::source();
+        let event = if granted {
+            Event::RoleGranted { role, account, by }
+        } else {
+            Event::RoleRevoked { role, account, by }
+        };
+        self.emit_event(event).expect("Notification Error");
+    }
+
+    /// Emit `Minted` along with the resulting balance and total supply.
+    fn emit_minted(&mut self, to: ActorId, value: U256) {
+        let balance = self.balance_of(to);
+        let total_supply = self.total_supply();
+        self.emit_event(Event::Minted { to, value, balance, total_supply })
+            .expect("Notification Error");
+    }
+
+    /// Emit `Burned` along with the resulting balance and total supply.
+    fn emit_burned(&mut self, from: ActorId, value: U256) {
+        let balance = self.balance_of(from);
+        let total_supply = self.total_supply();
+        self.emit_event(Event::Burned { from, value, balance, total_supply })
+            .expect("Notification Error");
+    }
+
+    fn emit_metadata_updated(&mut self) {
+        let event = Event::MetadataUpdated {
+            name: self.name(),
+            symbol: self.symbol(),
+            icon_uri: self.icon_uri(),
+        };
+        self.emit_event(event).expect("Notification Error");
+    }
+
+    fn emit_extended_metadata_updated(&mut self) {
+        let metadata = self.extended_metadata();
+        self.emit_event(Event::ExtendedMetadataUpdated { metadata })
+            .expect("Notification Error");
+    }
+
+    fn ensure_not_paused(&self) {
+        if self.get().paused {
+            panic!("Paused")
+        };
+    }
+
+    fn ensure_not_blacklisted(&self, account: ActorId) {
+        if self.get().blacklist.contains(&account) {
+            panic!("Account is blacklisted")
+        };
+    }
+
+    /// Fold the interest accrued since the last touch into the stored rebase index.
+    fn accrue(&mut self) {
+        let index = self.index();
+        let storage = self.get_mut();
+        storage.rebase_index = index;
+        storage.last_accrual_block = exec::block_height();
+    }
+
+    /// Ask the before-transfer hook about a transfer; an enforced hook that fails or doesn't
+    /// reply `true` rejects it. Runs before any state change, as it awaits a reply.
+    async fn run_before_hook(&self, from: ActorId, to: ActorId, value: U256) {
+        let hooks = &self.get().transfer_hooks;
+        let Some(hook) = hooks.before else {
+            return;
+        };
+        let enforce = hooks.enforce;
+        let message = TransferHookMessage { token: exec::program_id(), from, to, value, after: false };
+        let allowed = match msg::send_bytes_for_reply(hook, message.encode(), 0, 0) {
+            Ok(future) => match future.await {
+                Ok(reply) => bool::decode(&mut reply.as_slice()).unwrap_or(false),
+                Err(_) => false,
+            },
+            Err(_) => false,
+        };
+        if enforce && !allowed {
+            panic!("Transfer rejected by hook")
+        };
+    }
+
+    /// Notify the after-transfer hook of a completed transfer, without waiting for a reply.
+    fn notify_after_hook(&self, from: ActorId, to: ActorId, value: U256) {
+        if let Some(hook) = self.get().transfer_hooks.after {
+            let message = TransferHookMessage { token: exec::program_id(), from, to, value, after: true };
+            // A failing hook must not fail the transfer
+            let _ = msg::send_bytes(hook, message.encode(), 0);
+        }
+    }
+
+    /// Why moving tokens from `from` to `to` is currently not allowed, if it isn't.
+    fn transfer_blocked(&self, from: ActorId, to: ActorId) -> Option<&'static str> {
+        let storage = self.get();
+        if storage.paused {
+            return Some("Paused");
+        }
+        if storage.soulbound {
+            return Some("Token is soulbound");
+        }
+        if storage.blacklist.contains(&from) || storage.blacklist.contains(&to) {
+            return Some("Account is blacklisted");
+        }
+        // Locks and streams check the real counterparties when they are created
+        if is_escrow(from, to) {
+            return None;
+        }
+        if storage.whitelist_only
+            && !(storage.whitelist.contains(&from) && storage.whitelist.contains(&to))
+        {
+            return Some("Account is not whitelisted");
+        }
+        if let Some(rule) = storage.tier_rules.get(&self.tier_of(from)) {
+            if self.tier_of(to) < rule.min_recipient_tier {
+                return Some("Recipient tier is below the minimum for the sender's tier");
+            }
+        }
+        None
+    }
+
+    fn ensure_can_transfer(&self, from: ActorId, to: ActorId) {
+        if let Some(reason) = self.transfer_blocked(from, to) {
+            panic!("{}", reason)
+        };
+    }
+
+    /// Shares of `account` that are still locked by vesting, vested or not.
+    fn locked_shares(&self, account: ActorId) -> U256 {
+        self.vesting_schedules(account)
+            .iter()
+            .fold(U256::zero(), |acc, schedule| {
+                acc.saturating_add(schedule.total - schedule.released)
+            })
+    }
+
+    fn frozen_shares(&self, account: ActorId) -> U256 {
+        self.get().frozen.get(&account).copied().unwrap_or_default()
+    }
+
+    fn to_shares(&self, tokens: U256) -> U256 {
+        tokens.saturating_mul(U256::from(REBASE_PRECISION)) / self.index()
+    }
+
+    fn to_tokens(&self, shares: U256) -> U256 {
+        shares.saturating_mul(self.index()) / U256::from(REBASE_PRECISION)
+    }
+
+    /// Tokens `shares` were worth when snapshot `snapshot_id` was taken.
+    fn to_tokens_at(&self, shares: U256, snapshot_id: u64) -> U256 {
+        let index = self.get().snapshot_indexes[snapshot_id as usize - 1];
+        shares.saturating_mul(index) / U256::from(REBASE_PRECISION)
+    }
+
+    /// Count `shares` against the spending limit and tier limit of `from`, both set in tokens;
+    /// panics if it exceeds either.
+    fn spend(&mut self, from: ActorId, shares: U256) {
+        // Payouts of escrowed tokens were counted when deposited
+        if from == exec::program_id() {
+            return;
+        }
+        let value = self.to_tokens(shares);
+        self.spend_tier(from, value);
+        let Some(limit) = self.get_mut().spending_limits.get_mut(&from) else {
+            return;
+        };
+        let block = exec::block_height();
+        let spent = limit.spent_at(block).saturating_add(value);
+        if spent > limit.limit {
+            panic!("Spending limit exceeded")
+        };
+        if block.saturating_sub(limit.period_start) >= limit.period_blocks {
+            limit.period_start = block;
+        }
+        limit.spent = spent;
+    }
+
+    fn spend_tier(&mut self, from: ActorId, value: U256) {
+        let tier = self.tier_of(from);
+        let Some((max, period_blocks)) = self.tier_rule(tier).and_then(|rule| rule.max_per_period) else {
+            return;
+        };
+        let block = exec::block_height();
+        let storage = self.get_mut();
+        let (start, spent) = match storage.tier_spent.get(&from) {
+            Some((start, spent)) if block.saturating_sub(*start) < period_blocks => (*start, *spent),
+            _ => (block, U256::zero()),
+        };
+        let spent = spent.saturating_add(value);
+        if spent > max {
+            panic!("Tier {} may transfer at most {} per {} blocks", tier, max, period_blocks)
+        };
+        storage.tier_spent.insert(from, (start, spent));
+    }
+
+    /// Move `shares` from `from` to `to` like `do_transfers`; returns the shares `to` received.
+    async fn do_transfer(&mut self, spender: Option<ActorId>, from: ActorId, to: ActorId, shares: U256) -> U256 {
+        self.do_transfers(spender, &[(from, to, shares)]).await[0]
+    }
+
+    /// Move the shares of each (from, to, shares) with the transfer checks, fee and auto-burn,
+    /// out of the allowance of `spender`, the caller, if given. Every user-initiated balance
+    /// move goes through here. The before-transfer hook is asked about all of them first, so
+    /// the moves themselves happen without awaiting and a failing one reverts them all; the
+    /// after-transfer hook is notified of each. Returns the shares each recipient received;
+    /// callers authorize the senders and emit events.
+    async fn do_transfers(
+        &mut self,
+        spender: Option<ActorId>,
+        transfers: &[(ActorId, ActorId, U256)],
+    ) -> Vec<U256> {
+        for (from, to, shares) in transfers {
+            self.run_before_hook(*from, *to, self.to_tokens(*shares)).await;
+        }
+
+        let mut received = Vec::with_capacity(transfers.len());
+        for (from, to, shares) in transfers.iter().copied() {
+            self.ensure_can_transfer(from, to);
+            self.spend(from, shares);
+            let prior = self.before_balance_change(&self.transfer_parties(from, to));
+            let net = self.charge_fee(spender, from, to, shares);
+            let net = self.auto_burn(spender, from, to, shares, net);
+            // Through the base service where it applies, for its standard `Transfer` event
+            let mutated = match spender {
+                Some(_) => self.vft.transfer_from(from, to, net),
+                None if from == msg::source() => self.vft.transfer(to, net),
+                None => utils::panicking(|| funcs::transfer(Storage::balances(), from, to, net)),
+            };
+            self.after_balance_change(prior);
+            received.push(if mutated { net } else { U256::zero() });
+        }
+
+        for ((from, to, shares), net) in transfers.iter().zip(&received) {
+            if !net.is_zero() {
+                self.notify_after_hook(*from, *to, self.to_tokens(*shares));
+            }
+        }
+        received
+    }
+
+    /// Move `value` from `from` to `to` as is, without transfer checks, fee, auto-burn or
+    /// hooks; for admin-forced moves and reverting a transfer.
+    fn move_balance(&mut self, from: ActorId, to: ActorId, value: U256) -> bool {
+        let prior = self.before_balance_change(&[from, to]);
+        let mutated = utils::panicking(|| funcs::transfer(Storage::balances(), from, to, value));
+        self.after_balance_change(prior);
+        mutated
+    }
+
+    /// Accounts whose balances a transfer from `from` to `to` may touch.
+    fn transfer_parties(&self, from: ActorId, to: ActorId) -> Vec<ActorId> {
+        let mut parties = vec![from, to];
+        if let Some(collector) = self.get().fee_collector {
+            parties.push(collector);
+        }
+        parties
+    }
+
+    /// Capture the balances of `accounts` and the total supply ahead of a mutation.
+    fn before_balance_change(&self, accounts: &[ActorId]) -> PriorBalances {
+        let mut balances: Vec<(ActorId, U256)> = Vec::with_capacity(accounts.len());
+        for account in accounts {
+            if !balances.iter().any(|(seen, _)| seen == account) {
+                balances.push((*account, funcs::balance_of(Storage::balances(), *account)));
+            }
+        }
+        PriorBalances {
+            balances,
+            total_supply: *Storage::total_supply(),
+        }
+    }
+
+    /// Lock checks and bookkeeping for every balance and supply that changed since
+    /// `before_balance_change`; panics if a balance drops below its locked and frozen amounts.
+    fn after_balance_change(&mut self, prior: PriorBalances) {
+        self.accrue();
+        for (account, old) in prior.balances {
+            let new = funcs::balance_of(Storage::balances(), account);
+            if new < old && new < self.locked_shares(account) {
+                panic!("Tokens are locked")
+            };
+            if new < old && new < self.frozen_shares(account).saturating_add(self.locked_shares(account)) {
+                panic!("Tokens are frozen")
+            };
+            if new != old {
+                self.record_snapshot_value(Some(account), old);
+                self.update_balance_index(account, old, new);
+                if let Some(delegate) = self.delegates(account) {
+                    if new > old {
+                        self.move_votes(delegate, new - old, true);
+                    } else {
+                        self.move_votes(delegate, old - new, false);
+                    }
+                }
+            }
+        }
+        let new_total = *Storage::total_supply();
+        if new_total != prior.total_supply {
+            self.record_snapshot_value(None, prior.total_supply);
+            self.record_supply_change(prior.total_supply, new_total);
+        }
+    }
+
+    /// Append to the supply history ring buffer, overwriting the oldest entry once full.
+    fn record_supply_change(&mut self, old: U256, new: U256) {
+        let (old, new) = (self.to_tokens(old), self.to_tokens(new));
+        let change = SupplyChange {
+            block: exec::block_height(),
+            actor: msg::source(),
+            increase: new > old,
+            amount: if new > old { new - old } else { old - new },
+            new_total: new,
+        };
+        let storage = self.get_mut();
+        if storage.supply_history.len() < MAX_SUPPLY_HISTORY {
+            storage.supply_history.push(change);
+        } else {
+            storage.supply_history[storage.supply_history_next] = change;
+        }
+        storage.supply_history_next = (storage.supply_history_next + 1) % MAX_SUPPLY_HISTORY;
+    }
+
+    fn holder(&self, account: ActorId) -> (ActorId, U256) {
+        (account, self.balance_of(account))
+    }
+
+    /// Append to the burn log ring buffer, overwriting the oldest entry once full.
+    fn record_burn(&mut self, record: BurnRecord) {
+        let storage = self.get_mut();
+        if storage.burn_log.len() < MAX_BURN_LOG {
+            storage.burn_log.push(record);
+        } else {
+            storage.burn_log[storage.burn_log_next] = record;
+        }
+        storage.burn_log_next = (storage.burn_log_next + 1) % MAX_BURN_LOG;
+    }
+
+    /// Keep the holders ordered by balance for `top_holders` and `stats` and by account for
+    /// the holder queries and exports, as the balances map itself has no stable order.
+    fn update_balance_index(&mut self, account: ActorId, old: U256, new: U256) {
+        let storage = self.get_mut();
+        storage.balance_index.remove(&(old, account));
+        if new.is_zero() {
+            storage.holder_accounts.remove(&account);
+        } else {
+            storage.balance_index.insert((new, account));
+            storage.holder_accounts.insert(account);
+        }
+    }
+
+    /// Copy-on-write: keep the value an account (or the supply, for `None`) had at the
+    /// latest snapshot the first time it changes after that snapshot.
+    fn record_snapshot_value(&mut self, account: Option<ActorId>, old: U256) {
+        let storage = self.get_mut();
+        let id = storage.snapshot_id;
+        if id == 0 {
+            return;
+        }
+        let snapshots = match account {
+            Some(account) => storage.account_snapshots.entry(account).or_default(),
+            None => &mut storage.supply_snapshots,
+        };
+        match snapshots.last() {
+            Some((last, _)) if *last >= id => {}
+            _ => snapshots.push((id, old)),
+        }
+    }
+
+    /// Shares currently delegated to `account`.
+    fn vote_shares(&self, account: ActorId) -> U256 {
+        self.get()
+            .vote_checkpoints
+            .get(&account)
+            .and_then(|checkpoints| checkpoints.last())
+            .map_or(U256::zero(), |(_, votes)| *votes)
+    }
+
+    /// Add (or remove) `amount` vote shares of `delegate`, checkpointed at the current block.
+    fn move_votes(&mut self, delegate: ActorId, amount: U256, add: bool) {
+        if amount.is_zero() {
+            return;
+        }
+        let previous = self.vote_shares(delegate);
+        let new = if add {
+            previous.saturating_add(amount)
+        } else {
+            previous.saturating_sub(amount)
+        };
+        let block = exec::block_height();
+        let checkpoints = self.get_mut().vote_checkpoints.entry(delegate).or_default();
+        match checkpoints.last_mut() {
+            Some((height, votes)) if *height == block => *votes = new,
+            _ => checkpoints.push((block, new)),
+        }
+        let (previous, new) = (self.to_tokens(previous), self.to_tokens(new));
+        self.emit_event(Event::DelegateVotesChanged { delegate, previous, new })
+            .expect("Notification Error");
+    }
+
+    fn take_snapshot(&mut self) -> u64 {
+        self.accrue();
+        let storage = self.get_mut();
+        storage.snapshot_indexes.push(storage.rebase_index);
+        storage.snapshot_id += 1;
+        let id = storage.snapshot_id;
+        self.emit_event(Event::Snapshot { id })
+            .expect("Notification Error");
+        id
+    }
+
+    /// Unclaimed share of dividend `id` owed to `account`.
+    fn dividend_share(&self, id: u32, account: ActorId) -> u128 {
+        let storage = self.get();
+        if account == exec::program_id() || storage.dividends_claimed.contains(&(id, account)) {
+            return 0;
+        }
+        let dividend = &storage.dividends[id as usize];
+        let balance = self.balance_of_at(account, dividend.snapshot_id);
+        let supply = self.dividend_supply(dividend.snapshot_id);
+        (U256::from(dividend.amount).saturating_mul(balance) / supply).low_u128()
+    }
+
+    /// Supply a dividend at `snapshot_id` is shared over: everything but the escrow, which
+    /// can never claim.
+    fn dividend_supply(&self, snapshot_id: u64) -> U256 {
+        let escrow = self.balance_of_at(exec::program_id(), snapshot_id);
+        self.total_supply_at(snapshot_id).saturating_sub(escrow)
+    }
+
+    fn ensure_valid_snapshot(&self, snapshot_id: u64) {
+        if snapshot_id == 0 || snapshot_id > self.get().snapshot_id {
+            panic!("Invalid snapshot")
+        };
+    }
+
+    /// Move the transfer fee on `value` from `from` to the fee collector and return what is
+    /// left for `to`. Delegated transfers pay the fee out of `spender`'s allowance.
+    fn charge_fee(&mut self, spender: Option<ActorId>, from: ActorId, to: ActorId, value: U256) -> U256 {
+        let storage = self.get();
+        let Some(collector) = storage.fee_collector else {
+            return value;
+        };
+        if storage.fee_exempt.contains(&from) || storage.fee_exempt.contains(&to) {
+            return value;
+        }
+        if is_escrow(from, to) {
+            return value;
+        }
+        let fee = value.saturating_mul(U256::from(storage.transfer_fee_bps)) / U256::from(10_000u64);
+        if fee.is_zero() {
+            return value;
+        }
+        utils::panicking(|| match spender {
+            Some(spender) => funcs::transfer_from(
+                Storage::allowances(),
+                Storage::balances(),
+                spender,
+                from,
+                collector,
+                fee,
+            ),
+            None => funcs::transfer(Storage::balances(), from, collector, fee),
+        });
+
+        let net = value - fee;
+        let (gross, net_tokens, fee) = (self.to_tokens(value), self.to_tokens(net), self.to_tokens(fee));
+        self.emit_event(Event::FeeCharged { from, to, gross, net: net_tokens, fee })
+            .expect("Notification Error");
+        net
+    }
+
+    /// Burn the auto-burn share of `value` from `from` out of the `net` amount still to be
+    /// transferred and return what is left. Delegated transfers burn out of `spender`'s allowance.
+    fn auto_burn(&mut self, spender: Option<ActorId>, from: ActorId, to: ActorId, value: U256, net: U256) -> U256 {
+        if is_escrow(from, to) {
+            return net;
+        }
+        let bps = self.get().auto_burn_bps;
+        let burned = (value.saturating_mul(U256::from(bps)) / U256::from(10_000u64)).min(net);
+        if burned.is_zero() {
+            return net;
+        }
+        if let Some(spender) = spender {
+            spend_allowance(from, spender, burned);
+        }
+        utils::panicking(|| burn(Storage::balances(), Storage::total_supply(), from, burned));
+
+        // Counted in the total burned, but not as burned by the sender
+        let value = self.to_tokens(burned);
+        let storage = self.get_mut();
+        storage.auto_burned = storage.auto_burned.saturating_add(value);
+        storage.total_burned = storage.total_burned.saturating_add(value);
+        self.emit_event(Event::AutoBurned { from, value })
+            .expect("Notification Error");
+        net - burned
+    }
+
+    /// Allowance of `spender` over `owner`'s shares; zero once expired.
+    fn allowance_shares(&self, owner: ActorId, spender: ActorId) -> U256 {
+        if self.is_allowance_expired(owner, spender) {
+            return U256::zero();
+        }
+        funcs::allowance(Storage::allowances(), owner, spender)
+    }
+
+    fn is_allowance_expired(&self, owner: ActorId, spender: ActorId) -> bool {
+        self.allowance_expiry(owner, spender)
+            .is_some_and(|deadline| exec::block_timestamp() > deadline)
+    }
+
+    fn ensure_no_multisig(&self) {
+        if self.get().mint_multisig.is_some() {
+            panic!("Requires multisig approval")
+        };
+    }
+
+    /// Panics if a mint multisig could no longer be approved by `admins` admins.
+    fn ensure_enough_signers(&self, admins: usize) {
+        if self.get().mint_multisig.is_some_and(|(_, required)| required as usize > admins) {
+            panic!("Too few admins left for the multisig")
+        };
+    }
+
+    /// Carry out an approved multisig action. Returns false only for a mint that didn't mint.
+    fn execute_multisig_action(&mut self, action: MultisigAction) -> bool {
+        match action {
+            MultisigAction::Mint { to, value } => {
+                let mutated = self.do_mint(to, self.to_shares(value));
+                if mutated {
+                    self.emit_minted(to, value);
+                }
+                return mutated;
+            }
+            MultisigAction::SetMultisig(config) => {
+                if let Some((_, required)) = config {
+                    if required == 0 || required as usize > self.get().admins.len() {
+                        panic!("Invalid number of approvals")
+                    };
+                }
+                self.get_mut().mint_multisig = config;
+                self.emit_event(Event::MintMultisigSet { config })
+                    .expect("Notification Error");
+            }
+            MultisigAction::AddAdmin(account) => {
+                self.ensure_not_blacklisted(account);
+                if self.get_mut().admins.insert(account) {
+                    self.emit_role_change(Role::Admin, account, true);
+                }
+            }
+            MultisigAction::RemoveAdmin(account) => {
+                let admins = &self.get().admins;
+                if admins.len() == 1 && admins.contains(&account) {
+                    panic!("Cannot revoke the last admin")
+                };
+                if admins.contains(&account) {
+                    self.ensure_enough_signers(admins.len() - 1);
+                }
+                if self.get_mut().admins.remove(&account) {
+                    self.emit_role_change(Role::Admin, account, false);
+                }
+            }
+        }
+        true
+    }
+
+    /// Count `value` towards the mints of the current threshold period, so splitting a
+    /// large mint over several calls still needs multisig approval.
+    fn ensure_below_mint_threshold(&mut self, value: U256) {
+        let Some((threshold, _)) = self.get().mint_multisig else {
+            return;
+        };
+        let now = exec::block_timestamp();
+        let storage = self.get_mut();
+        if now.saturating_sub(storage.threshold_period_start) >= MINT_THRESHOLD_PERIOD_MS {
+            storage.threshold_period_start = now;
+            storage.minted_in_threshold_period = U256::zero();
+        }
+        let minted = storage.minted_in_threshold_period.saturating_add(value);
+        if minted > threshold {
+            panic!("Mint above threshold requires multisig approval")
+        };
+        storage.minted_in_threshold_period = minted;
+    }
+
+    /// Why minting `value` shares to `to` is currently not allowed, if it isn't.
+    fn mint_blocked(&self, to: ActorId, value: U256) -> Option<&'static str> {
+        let storage = self.get();
+        if storage.paused {
+            return Some("Paused");
+        }
+        if storage.blacklist.contains(&to) {
+            return Some("Account is blacklisted");
+        }
+        if self
+            .mintable_remaining()
+            .is_some_and(|remaining| self.to_tokens(value) > remaining)
+        {
+            return Some("Max supply exceeded");
+        }
+        None
+    }
+
+    /// Credit `value` to `to` and account for it; callers check roles and emit events.
+    fn do_mint(&mut self, to: ActorId, value: U256) -> bool {
+        if let Some(reason) = self.mint_blocked(to, value) {
+            panic!("{}", reason)
+        };
+        let prior = self.before_balance_change(&[to]);
+        let mutated = utils::panicking(|| {
+            mint(Storage::balances(), Storage::total_supply(), to, value)
+        });
+        self.after_balance_change(prior);
+        if mutated {
+            let minted = self.to_tokens(value);
+            let storage = self.get_mut();
+            storage.total_minted = storage.total_minted.saturating_add(minted);
+            self.record_account_operation(to, true, value);
+        }
+        mutated
+    }
+
+    /// Count a mint of `shares` by the calling minter in its stats, in tokens.
+    fn record_minter_stats(&mut self, shares: U256) {
+        let value = self.to_tokens(shares);
+        let stats = self.get_mut().minter_stats.entry(msg::source()).or_default();
+        stats.minted = stats.minted.saturating_add(value);
+        stats.mints = stats.mints.saturating_add(1);
+        stats.last_mint_block = exec::block_height();
+    }
+
+    /// Debit `value` from `from` and account for it; callers check roles and emit events.
+    fn do_burn(&mut self, from: ActorId, value: U256) -> bool {
+        self.ensure_not_paused();
+        let mutated = self.burn_balance(from, value);
+        if mutated {
+            self.track_burn_volume(value);
+        }
+        mutated
+    }
+
+    /// Debit `value` from `from` and count it as burned, without pause or circuit breaker checks.
+    fn burn_balance(&mut self, from: ActorId, value: U256) -> bool {
+        let prior = self.before_balance_change(&[from]);
+        let mutated = utils::panicking(|| {
+            burn(Storage::balances(), Storage::total_supply(), from, value)
+        });
+        self.after_balance_change(prior);
+        if mutated {
+            self.count_burned(value);
+            self.record_account_operation(from, false, value);
+        }
+        mutated
+    }
+
+    /// Append a compliance action by the caller to the compliance log and return the caller.
+    fn record_compliance(&mut self, action: ComplianceAction, account: ActorId, reason: &str) -> ActorId {
+        if reason.len() > MAX_REASON_LENGTH {
+            panic!("Reason is too long")
+        };
+        let by = msg::source();
+        self.get_mut().compliance_log.push(ComplianceRecord {
+            block: exec::block_height(),
+            action,
+            account,
+            by,
+            reason: reason.into(),
+        });
+        by
+    }
+
+    /// Append to the mint and burn history of `account`, dropping the oldest entry once full.
+    fn record_account_operation(&mut self, account: ActorId, minted: bool, shares: U256) {
+        let value = self.to_tokens(shares);
+        let actor = msg::source();
+        let storage = self.get_mut();
+        let role = if storage.minters.contains(&actor) {
+            Some(Role::Minter)
+        } else if storage.burners.contains(&actor) {
+            Some(Role::Burner)
+        } else if storage.bridges.contains(&actor) {
+            Some(Role::Bridge)
+        } else if storage.admins.contains(&actor) {
+            Some(Role::Admin)
+        } else {
+            None
+        };
+        let history = storage.account_history.entry(account).or_default();
+        if history.len() >= MAX_ACCOUNT_HISTORY {
+            history.remove(0);
+        }
+        history.push(AccountOperation {
+            block: exec::block_height(),
+            minted,
+            actor,
+            role,
+            value,
+        });
+    }
+
+    /// Add `shares`, in tokens, to the total burned and to what the caller burned.
+    fn count_burned(&mut self, shares: U256) {
+        let value = self.to_tokens(shares);
+        let storage = self.get_mut();
+        storage.total_burned = storage.total_burned.saturating_add(value);
+        let burned = storage.burned_by.entry(msg::source()).or_default();
+        *burned = burned.saturating_add(value);
+    }
+
+    /// Pause once burns in the current window exceed the configured share of the supply
+    /// the window started with.
+    fn track_burn_volume(&mut self, value: U256) {
+        let storage = self.get_mut();
+        let Some((bps, window_ms)) = storage.burn_circuit_breaker else {
+            return;
+        };
+        let now = exec::block_timestamp();
+        if now.saturating_sub(storage.burn_window_start) > window_ms {
+            storage.burn_window_start = now;
+            storage.burned_in_window = U256::zero();
+        }
+        storage.burned_in_window = storage.burned_in_window.saturating_add(value);
+
+        let burned = storage.burned_in_window;
+        let window_supply = Storage::total_supply().saturating_add(burned);
+        if burned.saturating_mul(U256::from(10_000u64)) > window_supply.saturating_mul(U256::from(bps)) {
+            storage.paused = true;
+            self.emit_event(Event::CircuitBreakerTripped { burned, window_ms })
+                .expect("Notification Error");
+        }
+    }
+}
+impl AsRef<VftService> for ExtendedService {
+    fn as_ref(&self) -> &VftService {
+        &self.vft
+    }
+}
+
+/// Check an sr25519 signature by `signer` over `message`, either raw or wrapped in
+/// `<Bytes>..</Bytes>` as done by wallet extensions when signing raw data.
+pub fn verify_signature(signer: ActorId, message: &[u8], signature: &[u8]) -> bool {
+    let Ok(public_key) = PublicKey::from_bytes(signer.as_ref()) else {
+        return false;
+    };
+    let Ok(signature) = Signature::from_bytes(signature) else {
+        return false;
+    };
+    let mut wrapped = b"<Bytes>".to_vec();
+    wrapped.extend_from_slice(message);
+    wrapped.extend_from_slice(b"</Bytes>");
+
+    public_key.verify_simple(b"substrate", message, &signature).is_ok()
+        || public_key.verify_simple(b"substrate", &wrapped, &signature).is_ok()
+}
+
+/// Account bound to a VNFT token: keccak256("vnft-token-account" ++ program id ++ token id).
+/// Must match the derivation used by the VNFT program.
+pub fn token_account(nft_program: ActorId, token_id: U256) -> ActorId {
+    let mut hasher = Keccak256::new();
+    hasher.update(b"vnft-token-account");
+    hasher.update(nft_program.as_ref());
+    hasher.update(token_id.encode());
+    let hash: [u8; 32] = hasher.finalize().into();
+    hash.into()
+}
+
+/// Root of the Merkle tree containing `leaf` at `index`, given its sibling hashes bottom-up.
+/// Each bit of `index` tells whether the node at that level is a right child.
+pub fn merkle_root(leaf: [u8; 32], index: u32, proof: &[[u8; 32]]) -> [u8; 32] {
+    let mut node = leaf;
+    let mut position = index;
+    for sibling in proof {
+        let mut hasher = Keccak256::new();
+        if position & 1 == 0 {
+            hasher.update(node);
+            hasher.update(sibling);
+        } else {
+            hasher.update(sibling);
+            hasher.update(node);
+        }
+        node = hasher.finalize().into();
+        position >>= 1;
+    }
+    node
+}
+
+/// Value recorded for the first snapshot at or after `snapshot_id`; `None` means the value
+/// hasn't changed since and the current one applies.
+fn snapshot_value(snapshots: &[(u64, U256)], snapshot_id: u64) -> Option<U256> {
+    let index = snapshots.partition_point(|(id, _)| *id < snapshot_id);
+    snapshots.get(index).map(|(_, value)| *value)
+}
+
+/// Whether a move is into or out of the tokens the program holds in escrow for locks and
+/// streams, which are neither charged nor checked against whitelist and tier rules again.
+fn is_escrow(from: ActorId, to: ActorId) -> bool {
+    let program = exec::program_id();
+    from == program || to == program
+}
+
+/// Lower the allowance of `spender` over `owner`'s tokens by `value`; panics if it is too low.
+fn spend_allowance(owner: ActorId, spender: ActorId, value: U256) {
+    let allowance = funcs::allowance(Storage::allowances(), owner, spender);
+    if allowance < value {
+        panic!("Insufficient allowance")
+    };
+    funcs::approve(Storage::allowances(), owner, spender, allowance - value);
 }
 
 /// Mint VFT tokens with overflow check.
@@ -0,0 +1,222 @@
+use app::services::service::{BurnRecord, MAX_REASON_LENGTH};
+use sails_rs::{
+    gtest::{Program, System},
+    prelude::*,
+};
+use wasm::WASM_BINARY;
+
+const PROGRAM_ID: u64 = 100;
+
+const ADMIN: u64 = 42;
+const ALICE: u64 = 43;
+const BOB: u64 = 44;
+
+fn actor(id: u64) -> ActorId {
+    ActorId::from(id)
+}
+
+fn system() -> System {
+    let system = System::new();
+    for user in [ADMIN, ALICE, BOB] {
+        system.mint_to(user, 100_000_000_000_000);
+    }
+    system
+}
+
+// Deploy the token with ADMIN as admin, minter and burner
+fn deploy(system: &System) -> Program<'_> {
+    let program = Program::from_binary_with_id(system, PROGRAM_ID, WASM_BINARY);
+    let no_max_supply: Option<U256> = None;
+    let payload = (
+        "New",
+        "Token".to_string(),
+        "TKN".to_string(),
+        18u8,
+        no_max_supply,
+        false,
+    )
+        .encode();
+    let message_id = program.send_bytes(ADMIN, payload);
+    assert!(system.run_next_block().succeed.contains(&message_id));
+    program
+}
+
+// Call `method` of the token service; returns the decoded reply, or the panic message if
+// the call failed
+fn call<T: Decode>(
+    system: &System,
+    program: &Program,
+    from: u64,
+    method: &str,
+    args: impl Encode,
+) -> Result<T, String> {
+    let mut payload = ("Vft", method).encode();
+    payload.extend(args.encode());
+    let message_id = program.send_bytes(from, payload);
+    let result = system.run_next_block();
+    let reply = result
+        .log()
+        .iter()
+        .find(|log| log.reply_to() == Some(message_id))
+        .expect("No reply");
+    if result.failed.contains(&message_id) {
+        return Err(String::from_utf8_lossy(reply.payload()).into_owned());
+    }
+    let (_, _, value) = <(String, String, T)>::decode(&mut reply.payload()).expect("Invalid reply");
+    Ok(value)
+}
+
+fn assert_panics<T: Decode>(result: Result<T, String>, expected: &str) {
+    match result {
+        Ok(_) => panic!("Expected panic {:?}", expected),
+        Err(error) => assert!(
+            error.contains(expected),
+            "{:?} does not contain {:?}",
+            error,
+            expected
+        ),
+    }
+}
+
+fn balance(system: &System, program: &Program, account: u64) -> U256 {
+    call(system, program, ADMIN, "BalanceOf", actor(account)).unwrap()
+}
+
+fn mint(system: &System, program: &Program, to: u64, value: u64) -> Result<bool, String> {
+    call(
+        system,
+        program,
+        ADMIN,
+        "Mint",
+        (actor(to), U256::from(value)),
+    )
+}
+
+#[test]
+fn burn_with_reason_burns_and_logs_the_reason() {
+    let system = system();
+    let program = deploy(&system);
+    assert!(mint(&system, &program, ALICE, 1_000).unwrap());
+
+    let burned: bool = call(
+        &system,
+        &program,
+        ADMIN,
+        "BurnWithReason",
+        (actor(ALICE), U256::from(400), "redemption".to_string()),
+    )
+    .unwrap();
+    assert!(burned);
+    assert_eq!(balance(&system, &program, ALICE), U256::from(600));
+
+    let (log, total): (Vec<BurnRecord>, u32) =
+        call(&system, &program, ADMIN, "BurnLog", (0u32, 10u32)).unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(log[0].from, actor(ALICE));
+    assert_eq!(log[0].value, U256::from(400));
+    assert_eq!(log[0].reason, "redemption");
+}
+
+#[test]
+fn burn_with_reason_rejects_long_reasons_and_non_burners() {
+    let system = system();
+    let program = deploy(&system);
+    assert!(mint(&system, &program, ALICE, 1_000).unwrap());
+
+    let reason = "x".repeat(MAX_REASON_LENGTH + 1);
+    let result = call::<bool>(
+        &system,
+        &program,
+        ADMIN,
+        "BurnWithReason",
+        (actor(ALICE), U256::from(1), reason),
+    );
+    assert_panics(result, "Reason is too long");
+    let result = call::<bool>(
+        &system,
+        &program,
+        BOB,
+        "BurnWithReason",
+        (actor(ALICE), U256::from(1), "clawback".to_string()),
+    );
+    assert_panics(result, "Not allowed to burn");
+    assert_eq!(balance(&system, &program, ALICE), U256::from(1_000));
+
+    // A reason of the maximum length is fine
+    let reason = "x".repeat(MAX_REASON_LENGTH);
+    let burned: bool = call(
+        &system,
+        &program,
+        ADMIN,
+        "BurnWithReason",
+        (actor(ALICE), U256::from(1), reason),
+    )
+    .unwrap();
+    assert!(burned);
+}
+
+#[test]
+fn mints_add_up_against_the_multisig_threshold() {
+    let system = system();
+    let program = deploy(&system);
+
+    let config = Some((U256::from(100), 1u32));
+    call::<()>(&system, &program, ADMIN, "SetMintMultisig", config).unwrap();
+    assert!(mint(&system, &program, ALICE, 60).unwrap());
+    // A second mint in the same period can't take the total above the threshold
+    assert_panics(
+        mint(&system, &program, BOB, 60),
+        "Mint above threshold requires multisig approval",
+    );
+    assert!(mint(&system, &program, BOB, 40).unwrap());
+    assert_eq!(balance(&system, &program, BOB), U256::from(40));
+
+    let result = call::<()>(&system, &program, ADMIN, "GrantMinterRole", actor(ALICE));
+    assert_panics(result, "Requires multisig approval");
+}
+
+#[test]
+fn mint_batch_rejects_an_empty_batch() {
+    let system = system();
+    let program = deploy(&system);
+
+    let empty: Vec<(ActorId, U256)> = Vec::new();
+    assert_panics(
+        call::<bool>(&system, &program, ADMIN, "MintBatch", empty),
+        "Empty batch",
+    );
+    let recipients = vec![(actor(ALICE), U256::from(10)), (actor(BOB), U256::from(20))];
+    assert!(call::<bool>(&system, &program, ADMIN, "MintBatch", recipients).unwrap());
+    assert_eq!(balance(&system, &program, BOB), U256::from(20));
+}
+
+#[test]
+fn recovery_rejects_the_program_escrow() {
+    let system = system();
+    let program = deploy(&system);
+
+    let result = call::<()>(
+        &system,
+        &program,
+        ADMIN,
+        "InitiateRecovery",
+        (program.id(), actor(ALICE)),
+    );
+    assert_panics(result, "Cannot recover the program's escrow");
+    let result = call::<()>(
+        &system,
+        &program,
+        ALICE,
+        "InitiateRecovery",
+        (actor(BOB), actor(ALICE)),
+    );
+    assert_panics(result, "Not admin");
+    call::<()>(
+        &system,
+        &program,
+        ADMIN,
+        "InitiateRecovery",
+        (actor(BOB), actor(ALICE)),
+    )
+    .unwrap();
+}
@@ -3,7 +3,7 @@
 
 use sails_rs::{
     collections::{HashMap},
-    gstd::{msg},
+    gstd::{exec, msg},
     prelude::*,
 };
 use sails_rs::collections::HashSet;
@@ -12,6 +12,8 @@ use vnft_service::utils::TokenId; // required: used as standalone type
 use vnft_service::{Service as VnftService, Storage};
 use vnft_service::utils::{Error, Result};
 use sails_rs::gstd::service;
+use pagination::paginate;
+use sha3::{Digest, Keccak256};
 
 #[derive(Default)]
 pub struct ExtendedStorage {
@@ -20,6 +22,16 @@ pub struct ExtendedStorage {
     burners: HashSet<ActorId>,
     admins: HashSet<ActorId>,
     token_metadata_by_id: HashMap<TokenId, TokenMetadata>,
+    operator_approvals: HashMap<ActorId, HashSet<ActorId>>,
+    companion_vft: Option<ActorId>,
+    trait_index: HashMap<(String, String), HashSet<TokenId>>,
+    max_per_owner: Option<u32>,
+    limit_exempts_privileged: bool,
+    burned: HashMap<TokenId, (ActorId, u64)>,
+    burned_order: Vec<TokenId>,
+    max_tombstones: u32,
+    listings: HashMap<TokenId, (ActorId, U256)>,
+    locked: HashSet<TokenId>,
 }
 
 #[derive(Default, Debug, Encode, Decode, TypeInfo, Clone)]
@@ -30,10 +42,15 @@ pub struct TokenMetadata {
     pub description: String,
     pub media: String, 
     pub reference: String, 
+    pub attributes: Vec<(String, String)>,
+    pub content_hash: Option<[u8; 32]>,
 }
 
 static mut EXTENDED_STORAGE: Option<ExtendedStorage> = None;
 
+// Maximum number of entries returned by a paginated query.
+pub const MAX_PAGE_SIZE: u32 = 100;
+
 #[derive(Encode, Decode, TypeInfo)]
 #[codec(crate = sails_rs::scale_codec)]
 #[scale_info(crate = sails_rs::scale_info)]
@@ -42,10 +59,65 @@ pub enum Event {
         to: ActorId,
         token_metadata: TokenMetadata,
     },
+    MintedBatch {
+        to: ActorId,
+        first_token_id: TokenId,
+        count: u32,
+    },
     Burned {
         from: ActorId,
         token_id: TokenId,
     },
+    MetadataUpdated {
+        token_id: TokenId,
+        token_metadata: TokenMetadata,
+    },
+    BatchBurned {
+        from: ActorId,
+        count: u32,
+        owners: Vec<(ActorId, u32)>,
+    },
+    ApprovalForAll {
+        owner: ActorId,
+        operator: ActorId,
+        approved: bool,
+    },
+    CompanionVftSet {
+        vft: ActorId,
+    },
+    TokenBalanceTransferred {
+        token_id: TokenId,
+        to: ActorId,
+        value: U256,
+    },
+    TransferredBatch {
+        from: ActorId,
+        transfers: Vec<(ActorId, TokenId)>,
+    },
+    OperatorTransferred {
+        operator: ActorId,
+        from: ActorId,
+        to: ActorId,
+        token_id: TokenId,
+    },
+    Listed {
+        token_id: TokenId,
+        seller: ActorId,
+        price: U256,
+    },
+    ListingCancelled {
+        token_id: TokenId,
+    },
+    LockSet {
+        token_id: TokenId,
+        locked: bool,
+    },
+    Sold {
+        token_id: TokenId,
+        seller: ActorId,
+        buyer: ActorId,
+        price: U256,
+    },
 }
 
 #[derive(Clone)]
@@ -98,39 +170,290 @@ impl ExtendedService {
         if !self.get().minters.contains(&msg::source()) {
             panic!("Not allowed to mint")
         };
+        self.ensure_within_owner_limit(to, 1);
+
+        self.mint_token(to, token_metadata.clone());
+        self.emit_event(Event::Minted { to, token_metadata })
+            .expect("Notification Error");
+    }
+
+    // Mint several tokens with sequential ids to `to` at once. Only minters can mint.
+    // The per-owner limit applies to the whole batch.
+    pub fn mint_batch(&mut self, to: ActorId, metadata: Vec<TokenMetadata>) {
+        if !self.get().minters.contains(&msg::source()) {
+            panic!("Not allowed to mint")
+        };
+        if metadata.is_empty() {
+            panic!("Empty batch")
+        };
+        self.ensure_within_owner_limit(to, metadata.len() as u32);
+
+        let first_token_id = self.get().token_id;
+        let count = metadata.len() as u32;
+        for token_metadata in metadata {
+            self.mint_token(to, token_metadata);
+        }
+        self.emit_event(Event::MintedBatch { to, first_token_id, count })
+            .expect("Notification Error");
+    }
+
+    // Replace the metadata of an existing token. Only minters can update.
+    pub fn update_metadata(&mut self, token_id: TokenId, token_metadata: TokenMetadata) {
+        if !self.get().minters.contains(&msg::source()) {
+            panic!("Not allowed to update metadata")
+        };
+        let Some(previous) = self.get().token_metadata_by_id.get(&token_id).cloned() else {
+            panic!("Token does not exist")
+        };
+        self.unindex_traits(token_id, &previous);
+        self.index_traits(token_id, &token_metadata);
+        self.get_mut()
+            .token_metadata_by_id
+            .insert(token_id, token_metadata.clone());
+        self.emit_event(Event::MetadataUpdated { token_id, token_metadata })
+            .expect("Notification Error");
+    }
+
+    // Burn a token. Only burners can burn. Its bound VFT balance is sent to its owner.
+    pub fn burn(&mut self, from: ActorId, token_id: TokenId) {
+        if !self.get().burners.contains(&msg::source()) {
+            panic!("Not allowed to burn")
+        };
+        let Some(owner) = Storage::owner_by_id().get(&token_id).copied() else {
+            panic!("Token does not exist")
+        };
+        self.burn_token(token_id);
+        self.release_token_balance(token_id, owner);
+        self.emit_event(Event::Burned { from, token_id })
+            .expect("Notification Error");
+    }
+
+    // Burn several tokens at once. Burners can burn any token; anyone else only tokens
+    // they own, are approved for, or operate for their owner. A missing or unauthorized
+    // id panics, which reverts the whole message, so no partial batch is ever applied.
+    // Bound VFT balances are sent to the tokens' owners.
+    pub fn burn_batch(&mut self, token_ids: Vec<TokenId>) {
+        if token_ids.is_empty() {
+            panic!("Empty batch")
+        };
+        let from = msg::source();
+        let is_burner = self.get().burners.contains(&from);
+        let mut owners: Vec<(ActorId, u32)> = Vec::new();
+        for token_id in token_ids.iter() {
+            let Some(owner) = Storage::owner_by_id().get(token_id).copied() else {
+                panic!("Token does not exist")
+            };
+            let approved = Storage::token_approvals().get(token_id) == Some(&from);
+            if !is_burner && owner != from && !approved && !self.is_approved_for_all(owner, from) {
+                panic!("Not allowed to burn")
+            };
+            match owners.iter_mut().find(|(seen, _)| *seen == owner) {
+                Some((_, count)) => *count += 1,
+                None => owners.push((owner, 1)),
+            }
+            self.burn_token(*token_id);
+            self.release_token_balance(*token_id, owner);
+        }
+        owners.sort_unstable();
+        self.emit_event(Event::BatchBurned { from, count: token_ids.len() as u32, owners })
+            .expect("Notification Error");
+    }
+
+    // Allow or forbid an operator to manage all of the caller's tokens.
+    pub fn set_approval_for_all(&mut self, operator: ActorId, approved: bool) {
+        let owner = msg::source();
+        if owner == operator {
+            panic!("Approve to caller")
+        };
+        let operator_approvals = &mut self.get_mut().operator_approvals;
+        if approved {
+            operator_approvals.entry(owner).or_insert_with(HashSet::new).insert(operator);
+        } else if let Some(operators) = operator_approvals.get_mut(&owner) {
+            operators.remove(&operator);
+            if operators.is_empty() {
+                operator_approvals.remove(&owner);
+            }
+        }
+        self.emit_event(Event::ApprovalForAll { owner, operator, approved })
+            .expect("Notification Error");
+    }
 
+    // Transfer one of the caller's tokens. Locked tokens can't move; a listing is dropped.
+    pub fn transfer(&mut self, to: ActorId, token_id: TokenId) {
+        self.ensure_unlocked(token_id);
+        self.vnft.transfer(to, token_id);
+        self.get_mut().listings.remove(&token_id);
+    }
+
+    // Transfer a token on behalf of its owner. Operators approved for all are
+    // handled here, everyone else goes through the base per-token approval.
+    // Locked tokens can't move; a listing is dropped.
+    pub fn transfer_from(&mut self, from: ActorId, to: ActorId, token_id: TokenId) {
+        let operator = msg::source();
+        self.ensure_unlocked(token_id);
+        self.get_mut().listings.remove(&token_id);
+        if !self.is_approved_for_all(from, operator) {
+            return self.vnft.transfer_from(from, to, token_id);
+        }
         utils::panicking(|| {
-            mint(
+            transfer(
                 Storage::owner_by_id(),
                 Storage::tokens_for_owner(),
-                &mut self.get_mut().token_metadata_by_id,
-                &mut self.get_mut().token_id,
+                Storage::token_approvals(),
+                from,
                 to,
-                token_metadata.clone(),
+                token_id,
             )
         });
-        self.emit_event(Event::Minted { to, token_metadata })
+        self.emit_event(Event::OperatorTransferred { operator, from, to, token_id })
             .expect("Notification Error");
     }
 
-    // Burn a token. Only burners can burn.
-    pub fn burn(&mut self, from: ActorId, token_id: TokenId) {
-        if !self.get().burners.contains(&msg::source()) {
-            panic!("Not allowed to burn")
+    // Move several of the caller's tokens at once, each to its own recipient. A token the
+    // caller doesn't own or a locked one panics, which reverts the whole message. Listings
+    // of moved tokens are dropped.
+    pub fn transfer_batch(&mut self, transfers: Vec<(ActorId, TokenId)>) {
+        let from = msg::source();
+        if transfers.is_empty() {
+            panic!("Empty batch")
+        };
+        for (to, token_id) in transfers.iter() {
+            self.ensure_unlocked(*token_id);
+            utils::panicking(|| {
+                transfer(
+                    Storage::owner_by_id(),
+                    Storage::tokens_for_owner(),
+                    Storage::token_approvals(),
+                    from,
+                    *to,
+                    *token_id,
+                )
+            });
+            self.get_mut().listings.remove(token_id);
+        }
+        self.emit_event(Event::TransferredBatch { from, transfers })
+            .expect("Notification Error");
+    }
+
+    // List an unlocked token for sale at a non-zero `price` in native value. Only the token
+    // owner can list; listing again updates the price.
+    pub fn list(&mut self, token_id: TokenId, price: U256) {
+        let seller = msg::source();
+        if Storage::owner_by_id().get(&token_id) != Some(&seller) {
+            panic!("Not token owner")
+        };
+        self.ensure_unlocked(token_id);
+        if price.is_zero() {
+            panic!("Price must be positive")
+        };
+        if price > U256::from(u128::MAX) {
+            panic!("Price is too large")
+        };
+        self.get_mut().listings.insert(token_id, (seller, price));
+        self.emit_event(Event::Listed { token_id, seller, price })
+            .expect("Notification Error");
+    }
+
+    // Withdraw a listing. Only the seller can cancel.
+    pub fn cancel_listing(&mut self, token_id: TokenId) {
+        let listings = &mut self.get_mut().listings;
+        match listings.get(&token_id) {
+            Some((seller, _)) if *seller == msg::source() => listings.remove(&token_id),
+            Some(_) => panic!("Not seller"),
+            None => panic!("Not listed"),
+        };
+        self.emit_event(Event::ListingCancelled { token_id })
+            .expect("Notification Error");
+    }
+
+    // Buy a listed token with the attached value. The price goes to the seller and any
+    // excess is refunded.
+    pub fn buy_listed(&mut self, token_id: TokenId) {
+        let buyer = msg::source();
+        let paid = msg::value();
+        let Some((seller, price)) = self.get_mut().listings.remove(&token_id) else {
+            panic!("Not listed")
+        };
+        self.ensure_unlocked(token_id);
+        if U256::from(paid) < price {
+            panic!("Insufficient payment")
         };
         utils::panicking(|| {
-            burn(
+            transfer(
                 Storage::owner_by_id(),
                 Storage::tokens_for_owner(),
                 Storage::token_approvals(),
-                &mut self.get_mut().token_metadata_by_id,
+                seller,
+                buyer,
                 token_id,
             )
         });
-        self.emit_event(Event::Burned { from, token_id })
+
+        let price_value = price.low_u128();
+        msg::send_bytes(seller, [], price_value).expect("Error sending payment");
+        if paid > price_value {
+            msg::send_bytes(buyer, [], paid - price_value).expect("Error sending refund");
+        }
+        self.emit_event(Event::Sold { token_id, seller, buyer, price })
+            .expect("Notification Error");
+    }
+
+    // Lock or unlock one of the caller's tokens. A locked token can't be transferred or
+    // listed; locking drops its listing.
+    pub fn set_locked(&mut self, token_id: TokenId, locked: bool) {
+        if Storage::owner_by_id().get(&token_id) != Some(&msg::source()) {
+            panic!("Not token owner")
+        };
+        let storage = self.get_mut();
+        if locked {
+            storage.locked.insert(token_id);
+            storage.listings.remove(&token_id);
+        } else {
+            storage.locked.remove(&token_id);
+        }
+        self.emit_event(Event::LockSet { token_id, locked })
+            .expect("Notification Error");
+    }
+
+    // Set the VFT program holding token-bound balances. Only admin can set.
+    pub fn set_companion_vft(&mut self, vft: ActorId) {
+        self.ensure_is_admin();
+        self.get_mut().companion_vft = Some(vft);
+        self.emit_event(Event::CompanionVftSet { vft })
             .expect("Notification Error");
     }
 
+    // Move the whole companion VFT balance bound to a token. Only the token owner can move it,
+    // so the balance follows the token on transfer; burning moves it to the owner.
+    // The VFT recognizes the bound account by re-deriving it from this program's id.
+    pub async fn transfer_token_balance(&mut self, token_id: TokenId, to: ActorId) -> U256 {
+        let Some(vft) = self.get().companion_vft else {
+            panic!("Companion VFT is not set")
+        };
+        let owner = Storage::owner_by_id().get(&token_id).copied();
+        if owner != Some(msg::source()) {
+            panic!("Not token owner")
+        };
+        self.move_token_balance(vft, token_id, to).await
+    }
+
+    // Limit how many tokens one address may hold through minting; `None` removes the
+    // limit. With `exempt_privileged`, admins and minters are not limited. Only admin can set.
+    pub fn set_max_per_owner(&mut self, max_per_owner: Option<u32>, exempt_privileged: bool) {
+        self.ensure_is_admin();
+        let storage = self.get_mut();
+        storage.max_per_owner = max_per_owner;
+        storage.limit_exempts_privileged = exempt_privileged;
+    }
+
+    // Keep burn records for the latest `max_tombstones` burned tokens; 0 disables
+    // them. Lowering the limit drops the oldest records. Only admin can set.
+    pub fn set_max_tombstones(&mut self, max_tombstones: u32) {
+        self.ensure_is_admin();
+        self.get_mut().max_tombstones = max_tombstones;
+        self.prune_tombstones();
+    }
+
     // Grant admin role. Only admin can grant.
     pub fn grant_admin_role(&mut self, to: ActorId) {
         self.ensure_is_admin();
@@ -191,6 +514,84 @@ impl ExtendedService {
         self.get().token_metadata_by_id.get(&token_id).cloned()
     }
 
+    // Query whether an operator may manage all tokens of an owner.
+    pub fn is_approved_for_all(&self, owner: ActorId, operator: ActorId) -> bool {
+        self.get()
+            .operator_approvals
+            .get(&owner)
+            .is_some_and(|operators| operators.contains(&operator))
+    }
+
+    // Query how many more tokens can be minted to an owner; `None` if unlimited.
+    pub fn mints_remaining_for(&self, owner: ActorId) -> Option<u32> {
+        let storage = self.get();
+        let limit = storage.max_per_owner?;
+        if storage.limit_exempts_privileged && self.is_privileged(&owner) {
+            return None;
+        }
+        let held = Storage::tokens_for_owner()
+            .get(&owner)
+            .map_or(0, |tokens| tokens.len() as u32);
+        Some(limit.saturating_sub(held))
+    }
+
+    // Query whether `data` matches the keccak256 content hash committed for a token.
+    // False if the token doesn't exist or was minted without a hash.
+    pub fn verify_content(&self, token_id: TokenId, data: Vec<u8>) -> bool {
+        let Some(expected) = self
+            .get()
+            .token_metadata_by_id
+            .get(&token_id)
+            .and_then(|metadata| metadata.content_hash)
+        else {
+            return false;
+        };
+        let hash: [u8; 32] = Keccak256::digest(&data).into();
+        hash == expected
+    }
+
+    // Query the seller and price of a listed token.
+    pub fn listing(&self, token_id: TokenId) -> Option<(ActorId, U256)> {
+        self.get().listings.get(&token_id).copied()
+    }
+
+    // Query whether a token is locked.
+    pub fn is_locked(&self, token_id: TokenId) -> bool {
+        self.get().locked.contains(&token_id)
+    }
+
+    // Query whether a token currently exists.
+    pub fn exists(&self, token_id: TokenId) -> bool {
+        Storage::owner_by_id().contains_key(&token_id)
+    }
+
+    // Query who burned a token and when (block timestamp), if its record is retained.
+    pub fn burn_info(&self, token_id: TokenId) -> Option<(ActorId, u64)> {
+        self.get().burned.get(&token_id).copied()
+    }
+
+    // Query the companion VFT account bound to a token.
+    pub fn token_account(&self, token_id: TokenId) -> ActorId {
+        token_account(exec::program_id(), token_id)
+    }
+
+    // Query ids of tokens having an attribute, served from the trait index.
+    pub fn tokens_with_trait(&self, trait_type: String, value: String) -> Vec<TokenId> {
+        self.get()
+            .trait_index
+            .get(&(trait_type, value))
+            .map(|tokens| tokens.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    // Query how many tokens have an attribute, for rarity displays.
+    pub fn trait_count(&self, trait_type: String, value: String) -> u32 {
+        self.get()
+            .trait_index
+            .get(&(trait_type, value))
+            .map_or(0, |tokens| tokens.len() as u32)
+    }
+
     // Query all tokens for an owner (returns vec, not map)
     pub fn tokens_for_owner(&self, owner: ActorId) -> Vec<(TokenId, TokenMetadata)> {
         Storage::tokens_for_owner()
@@ -206,6 +607,29 @@ impl ExtendedService {
             })
             .unwrap_or_else(|| Vec::new())
     }
+
+    // Query a page of an owner's tokens ordered by id, plus the owner's token count.
+    pub fn tokens_for_owner_page(
+        &self,
+        owner: ActorId,
+        start: u32,
+        limit: u32,
+    ) -> (Vec<(TokenId, TokenMetadata)>, u32) {
+        let mut token_ids: Vec<TokenId> = Storage::tokens_for_owner()
+            .get(&owner)
+            .map(|tokens| tokens.iter().copied().collect())
+            .unwrap_or_default();
+        token_ids.sort_unstable();
+        let (page, total) = paginate(token_ids.into_iter(), start, limit, MAX_PAGE_SIZE);
+        let page = page
+            .into_iter()
+            .filter_map(|token_id| {
+                self.token_metadata_by_id(token_id)
+                    .map(|metadata| (token_id, metadata))
+            })
+            .collect();
+        (page, total)
+    }
 }
 
 impl ExtendedService {
@@ -214,6 +638,136 @@ impl ExtendedService {
             panic!("Not admin")
         };
     }
+
+    fn ensure_unlocked(&self, token_id: TokenId) {
+        if self.get().locked.contains(&token_id) {
+            panic!("Token is locked")
+        };
+    }
+
+    fn is_privileged(&self, who: &ActorId) -> bool {
+        let storage = self.get();
+        storage.admins.contains(who) || storage.minters.contains(who)
+    }
+
+    // Panics if minting `count` more tokens to `to` would exceed the per-owner limit.
+    fn ensure_within_owner_limit(&self, to: ActorId, count: u32) {
+        if let Some(remaining) = self.mints_remaining_for(to) {
+            if count > remaining {
+                panic!("Per-owner limit reached")
+            }
+        }
+    }
+
+    // Mint the next token id to `to`, index its traits and register its bound account with
+    // the companion VFT, if any, so the VFT never sweeps it as dust.
+    fn mint_token(&mut self, to: ActorId, token_metadata: TokenMetadata) {
+        let token_id = self.get().token_id;
+        if let Some(vft) = self.get().companion_vft {
+            let mut request = "Vft".encode();
+            request.extend("RegisterTokenAccount".encode());
+            request.extend(token_id.encode());
+            msg::send_bytes(vft, request, 0).expect("Error sending message");
+        }
+        self.index_traits(token_id, &token_metadata);
+        utils::panicking(|| {
+            mint(
+                Storage::owner_by_id(),
+                Storage::tokens_for_owner(),
+                &mut self.get_mut().token_metadata_by_id,
+                &mut self.get_mut().token_id,
+                to,
+                token_metadata,
+            )
+        });
+    }
+
+    // Burn a token and drop it from the trait index. Panics if it doesn't exist.
+    fn burn_token(&mut self, token_id: TokenId) {
+        let metadata = self.get().token_metadata_by_id.get(&token_id).cloned();
+        utils::panicking(|| {
+            burn(
+                Storage::owner_by_id(),
+                Storage::tokens_for_owner(),
+                Storage::token_approvals(),
+                &mut self.get_mut().token_metadata_by_id,
+                token_id,
+            )
+        });
+        if let Some(metadata) = metadata {
+            self.unindex_traits(token_id, &metadata);
+        }
+
+        let storage = self.get_mut();
+        storage.listings.remove(&token_id);
+        storage.locked.remove(&token_id);
+        if storage.max_tombstones > 0 {
+            storage
+                .burned
+                .insert(token_id, (msg::source(), exec::block_timestamp()));
+            storage.burned_order.push(token_id);
+            self.prune_tombstones();
+        }
+    }
+
+    // Send the bound VFT balance of a just burned token to `to`, if a companion VFT is set;
+    // otherwise the balance would be stranded once the token is gone. The message is only
+    // dispatched if the burn commits, and the VFT doesn't need the token to still exist.
+    fn release_token_balance(&self, token_id: TokenId, to: ActorId) {
+        if let Some(vft) = self.get().companion_vft {
+            msg::send_bytes(vft, token_bound_transfer_request(token_id, to), 0)
+                .expect("Error sending message");
+        }
+    }
+
+    // Ask the companion VFT to move the balance bound to a token to `to`.
+    async fn move_token_balance(&mut self, vft: ActorId, token_id: TokenId, to: ActorId) -> U256 {
+        let request = token_bound_transfer_request(token_id, to);
+        let reply = msg::send_bytes_for_reply(vft, request, 0, 0)
+            .expect("Error sending message")
+            .await
+            .expect("Error in reply");
+        let (_, _, value) = <(String, String, U256)>::decode(&mut reply.as_slice())
+            .expect("Invalid reply");
+
+        self.emit_event(Event::TokenBalanceTransferred { token_id, to, value })
+            .expect("Notification Error");
+        value
+    }
+
+    // Drop the oldest burn records above the retention limit.
+    fn prune_tombstones(&mut self) {
+        let storage = self.get_mut();
+        let excess = storage
+            .burned_order
+            .len()
+            .saturating_sub(storage.max_tombstones as usize);
+        for token_id in storage.burned_order.drain(..excess) {
+            storage.burned.remove(&token_id);
+        }
+    }
+
+    fn index_traits(&mut self, token_id: TokenId, metadata: &TokenMetadata) {
+        let trait_index = &mut self.get_mut().trait_index;
+        for attribute in metadata.attributes.iter() {
+            trait_index
+                .entry(attribute.clone())
+                .or_insert_with(HashSet::new)
+                .insert(token_id);
+        }
+    }
+
+    fn unindex_traits(&mut self, token_id: TokenId, metadata: &TokenMetadata) {
+        let trait_index = &mut self.get_mut().trait_index;
+        for attribute in metadata.attributes.iter() {
+            if let Some(tokens) = trait_index.get_mut(attribute) {
+                tokens.remove(&token_id);
+                if tokens.is_empty() {
+                    trait_index.remove(attribute);
+                }
+            }
+        }
+    }
 }
 
 impl AsRef<VnftService> for ExtendedService {
@@ -222,6 +776,25 @@ impl AsRef<VnftService> for ExtendedService {
     }
 }
 
+// Account bound to a token: keccak256("vnft-token-account" ++ program id ++ token id).
+// Must match the derivation used by the companion VFT program.
+pub fn token_account(program_id: ActorId, token_id: TokenId) -> ActorId {
+    let mut hasher = Keccak256::new();
+    hasher.update(b"vnft-token-account");
+    hasher.update(program_id.as_ref());
+    hasher.update(token_id.encode());
+    let hash: [u8; 32] = hasher.finalize().into();
+    hash.into()
+}
+
+// Encoded `Vft/TokenBoundTransfer(token_id, to)` call to the companion VFT.
+fn token_bound_transfer_request(token_id: TokenId, to: ActorId) -> Vec<u8> {
+    let mut request = "Vft".encode();
+    request.extend("TokenBoundTransfer".encode());
+    request.extend((token_id, to).encode());
+    request
+}
+
 // Mint function - do not modify, reuse as-is.
 pub fn mint(
     owner_by_id: &mut HashMap<TokenId, ActorId>,
@@ -263,3 +836,32 @@ pub fn burn(
     token_metadata_by_id.remove(&token_id);
     Ok(())
 }
+
+// Transfer function - moves a token from its owner and clears its approval.
+pub fn transfer(
+    owner_by_id: &mut HashMap<TokenId, ActorId>,
+    tokens_for_owner: &mut HashMap<ActorId, HashSet<TokenId>>,
+    token_approvals: &mut HashMap<TokenId, ActorId>,
+    from: ActorId,
+    to: ActorId,
+    token_id: TokenId,
+) -> Result<()> {
+    if to == ActorId::zero() {
+        gstd::ext::panic("ZeroAddress".to_string())
+    }
+    let owner = *owner_by_id.get(&token_id)
+        .unwrap_or_else(|| gstd::ext::panic("TokenDoesNotExist".to_string()));
+    if owner != from {
+        gstd::ext::panic("AccessDenied".to_string())
+    }
+    if let Some(tokens) = tokens_for_owner.get_mut(&from) {
+        tokens.remove(&token_id);
+        if tokens.is_empty() {
+            tokens_for_owner.remove(&from);
+        }
+    }
+    tokens_for_owner.entry(to).or_insert_with(HashSet::new).insert(token_id);
+    owner_by_id.insert(token_id, to);
+    token_approvals.remove(&token_id);
+    Ok(())
+}
@@ -0,0 +1,223 @@
+use app::services::service::TokenMetadata;
+use sails_rs::{
+    gtest::{Program, System},
+    prelude::*,
+};
+use wasm::WASM_BINARY;
+
+const PROGRAM_ID: u64 = 100;
+
+const ADMIN: u64 = 42;
+const ALICE: u64 = 43;
+const BOB: u64 = 44;
+
+const PRICE: u128 = 10_000_000_000_000;
+
+fn actor(id: u64) -> ActorId {
+    ActorId::from(id)
+}
+
+fn system() -> System {
+    let system = System::new();
+    for user in [ADMIN, ALICE, BOB] {
+        system.mint_to(user, 100_000_000_000_000);
+    }
+    system
+}
+
+// Deploy the collection with ADMIN as admin, minter and burner
+fn deploy(system: &System) -> Program<'_> {
+    let program = Program::from_binary_with_id(system, PROGRAM_ID, WASM_BINARY);
+    let payload = ("New", "Collection".to_string(), "NFT".to_string()).encode();
+    let message_id = program.send_bytes(ADMIN, payload);
+    assert!(system.run_next_block().succeed.contains(&message_id));
+    program
+}
+
+// Call `method` of the collection service with `value` attached; returns the decoded
+// reply, or the panic message if the call failed
+fn call_with_value<T: Decode>(
+    system: &System,
+    program: &Program,
+    from: u64,
+    method: &str,
+    args: impl Encode,
+    value: u128,
+) -> Result<T, String> {
+    let mut payload = ("Service", method).encode();
+    payload.extend(args.encode());
+    let message_id = program.send_bytes_with_value(from, payload, value);
+    let result = system.run_next_block();
+    let reply = result
+        .log()
+        .iter()
+        .find(|log| log.reply_to() == Some(message_id))
+        .expect("No reply");
+    if result.failed.contains(&message_id) {
+        return Err(String::from_utf8_lossy(reply.payload()).into_owned());
+    }
+    let (_, _, value) = <(String, String, T)>::decode(&mut reply.payload()).expect("Invalid reply");
+    Ok(value)
+}
+
+fn call<T: Decode>(
+    system: &System,
+    program: &Program,
+    from: u64,
+    method: &str,
+    args: impl Encode,
+) -> Result<T, String> {
+    call_with_value(system, program, from, method, args, 0)
+}
+
+fn assert_panics<T>(result: Result<T, String>, expected: &str) {
+    match result {
+        Ok(_) => panic!("Expected panic {:?}", expected),
+        Err(error) => assert!(
+            error.contains(expected),
+            "{:?} does not contain {:?}",
+            error,
+            expected
+        ),
+    }
+}
+
+// Mint a token to `to` and return its id
+fn mint(system: &System, program: &Program, to: u64) -> U256 {
+    let token_id: U256 = call(system, program, ADMIN, "TokenId", ()).unwrap();
+    call::<()>(
+        system,
+        program,
+        ADMIN,
+        "Mint",
+        (actor(to), TokenMetadata::default()),
+    )
+    .unwrap();
+    token_id
+}
+
+fn owns(system: &System, program: &Program, owner: u64, token_id: U256) -> bool {
+    let tokens: Vec<(U256, TokenMetadata)> =
+        call(system, program, ADMIN, "TokensForOwner", actor(owner)).unwrap();
+    tokens.iter().any(|(id, _)| *id == token_id)
+}
+
+fn listing(system: &System, program: &Program, token_id: U256) -> Option<(ActorId, U256)> {
+    call(system, program, ADMIN, "Listing", token_id).unwrap()
+}
+
+#[test]
+fn listed_token_can_be_bought() {
+    let system = system();
+    let program = deploy(&system);
+    let token_id = mint(&system, &program, ALICE);
+
+    let result = call::<()>(
+        &system,
+        &program,
+        BOB,
+        "List",
+        (token_id, U256::from(PRICE)),
+    );
+    assert_panics(result, "Not token owner");
+    assert_panics(
+        call::<()>(&system, &program, ALICE, "List", (token_id, U256::zero())),
+        "Price must be positive",
+    );
+    call::<()>(
+        &system,
+        &program,
+        ALICE,
+        "List",
+        (token_id, U256::from(PRICE)),
+    )
+    .unwrap();
+    assert_eq!(
+        listing(&system, &program, token_id),
+        Some((actor(ALICE), U256::from(PRICE)))
+    );
+
+    call_with_value::<()>(&system, &program, BOB, "BuyListed", token_id, PRICE).unwrap();
+    assert!(owns(&system, &program, BOB, token_id));
+    assert!(!owns(&system, &program, ALICE, token_id));
+    assert_eq!(listing(&system, &program, token_id), None);
+}
+
+#[test]
+fn underpaying_buyer_is_rejected() {
+    let system = system();
+    let program = deploy(&system);
+    let token_id = mint(&system, &program, ALICE);
+    call::<()>(
+        &system,
+        &program,
+        ALICE,
+        "List",
+        (token_id, U256::from(PRICE)),
+    )
+    .unwrap();
+
+    let result = call_with_value::<()>(&system, &program, BOB, "BuyListed", token_id, PRICE - 1);
+    assert_panics(result, "Insufficient payment");
+    assert!(owns(&system, &program, ALICE, token_id));
+    assert_eq!(
+        listing(&system, &program, token_id),
+        Some((actor(ALICE), U256::from(PRICE)))
+    );
+}
+
+#[test]
+fn cancelled_listing_can_no_longer_be_bought() {
+    let system = system();
+    let program = deploy(&system);
+    let token_id = mint(&system, &program, ALICE);
+    call::<()>(
+        &system,
+        &program,
+        ALICE,
+        "List",
+        (token_id, U256::from(PRICE)),
+    )
+    .unwrap();
+
+    assert_panics(
+        call::<()>(&system, &program, BOB, "CancelListing", token_id),
+        "Not seller",
+    );
+    call::<()>(&system, &program, ALICE, "CancelListing", token_id).unwrap();
+    assert_panics(
+        call::<()>(&system, &program, ALICE, "CancelListing", token_id),
+        "Not listed",
+    );
+
+    let result = call_with_value::<()>(&system, &program, BOB, "BuyListed", token_id, PRICE);
+    assert_panics(result, "Not listed");
+    assert!(owns(&system, &program, ALICE, token_id));
+}
+
+#[test]
+fn locked_token_cannot_be_listed() {
+    let system = system();
+    let program = deploy(&system);
+    let token_id = mint(&system, &program, ALICE);
+    call::<()>(
+        &system,
+        &program,
+        ALICE,
+        "List",
+        (token_id, U256::from(PRICE)),
+    )
+    .unwrap();
+
+    // Locking drops the listing
+    call::<()>(&system, &program, ALICE, "SetLocked", (token_id, true)).unwrap();
+    assert_eq!(listing(&system, &program, token_id), None);
+    let result = call::<()>(
+        &system,
+        &program,
+        ALICE,
+        "List",
+        (token_id, U256::from(PRICE)),
+    );
+    assert_panics(result, "Token is locked");
+}
@@ -0,0 +1,52 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+/// Return the page of `items` starting at index `start`, at most `limit` items long with
+/// `limit` clamped to `max`, together with the total number of items.
+/// Items must come in a deterministic order for pages to be consistent between calls.
+pub fn paginate<T>(
+    items: impl Iterator<Item = T>,
+    start: u32,
+    limit: u32,
+    max: u32,
+) -> (Vec<T>, u32) {
+    let start = start as usize;
+    let limit = limit.min(max) as usize;
+    let mut page = Vec::with_capacity(limit);
+    let mut total = 0u32;
+
+    for (index, item) in items.enumerate() {
+        if index >= start && page.len() < limit {
+            page.push(item);
+        }
+        total = total.saturating_add(1);
+    }
+    (page, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        assert_eq!(paginate(core::iter::empty::<u32>(), 0, 10, 100), (Vec::new(), 0));
+        assert_eq!(paginate(core::iter::empty::<u32>(), 5, 10, 100), (Vec::new(), 0));
+    }
+
+    #[test]
+    fn partial_last_page() {
+        assert_eq!(paginate(0..25u32, 20, 10, 100), ((20..25).collect(), 25));
+        assert_eq!(paginate(0..25u32, 25, 10, 100), (Vec::new(), 25));
+    }
+
+    #[test]
+    fn limit_clamped_to_max() {
+        let (page, total) = paginate(0..500u32, 0, 1_000, 100);
+        assert_eq!(page, (0..100).collect::<Vec<_>>());
+        assert_eq!(total, 500);
+    }
+}